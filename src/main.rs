@@ -1,9 +1,24 @@
 use genetic_algorithm_traits::Individual;
-use genetic_algorithm_tsp::distance_mat;
+use genetic_algorithm_tsp::{distance_mat, route};
 use genetic_algorithm_tsp_api::tsp_solver;
+use rand::{Rng, SeedableRng};
+use rocket::data::{self, ToByteUnit};
+use rocket::http;
+use rocket::request;
+use rocket::response::status;
 use rocket::serde::json;
+use rocket::tokio::io::AsyncBufReadExt;
+use rocket::{Build, Rocket, State};
 use serde::Deserialize;
 use serde::Serialize;
+use std::cmp;
+use std::collections::HashMap;
+use std::convert;
+use std::env;
+use std::io;
+use std::panic;
+use std::sync::{atomic, Arc, Mutex, RwLock};
+use std::thread;
 use std::time;
 #[macro_use]
 extern crate rocket;
@@ -14,110 +29,5711 @@ fn liveness_probe() -> json::Value {
     json::json!("alive")
 }
 
+/// Largest number of cities accepted if `TSP_MAX_CITIES` is not set.
+const DEFAULT_MAX_CITIES: usize = 1000;
+/// Largest number of cities solved exactly if `TSP_MAX_EXACT_N` is not set.
+const DEFAULT_MAX_EXACT_N: usize = 9;
+/// Longest possible tour accepted if `TSP_MAX_TOUR_DISTANCE` is not set.
+const DEFAULT_MAX_TOUR_DISTANCE: f64 = f64::MAX;
+
+/// Limits of the server that are shared by all handlers.
+#[derive(Serialize, Deserialize, Clone)]
+struct ApiConfig {
+    max_cities: usize,
+    max_exact_n: usize,
+    max_tour_distance: f64,
+    /// Most values returned per per-generation array of the solve meta, e.g.
+    /// `history`. Unlimited if `TSP_MAX_META_POINTS` is not set.
+    max_meta_points: Option<usize>,
+    default_generations: DefaultGenerations,
+}
+impl ApiConfig {
+    /// Read the configuration from the environment, falling back to the
+    /// defaults for all variables that are not set or cannot be parsed.
+    fn from_env() -> ApiConfig {
+        ApiConfig {
+            max_cities: env_or("TSP_MAX_CITIES", DEFAULT_MAX_CITIES),
+            max_exact_n: env_or("TSP_MAX_EXACT_N", DEFAULT_MAX_EXACT_N),
+            max_tour_distance: env_or("TSP_MAX_TOUR_DISTANCE", DEFAULT_MAX_TOUR_DISTANCE),
+            max_meta_points: env_opt("TSP_MAX_META_POINTS"),
+            default_generations: DefaultGenerations {
+                matrix: env_opt("TSP_MATRIX_DEFAULT_GENERATIONS"),
+                coordinates: env_opt("TSP_COORDINATES_DEFAULT_GENERATIONS"),
+                geo: env_opt("TSP_GEO_DEFAULT_GENERATIONS"),
+            },
+        }
+    }
+}
+
+/// The `n_generations` used by the solve-endpoints of every input type if
+/// neither the request nor its profile set it. Without a default,
+/// `n_generations` is required.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct DefaultGenerations {
+    /// `/tsp` and `/tsp/solve-matrix-stream`, set by
+    /// `TSP_MATRIX_DEFAULT_GENERATIONS`.
+    matrix: Option<usize>,
+    /// `/tsp/coordinates/3d`, set by `TSP_COORDINATES_DEFAULT_GENERATIONS`.
+    coordinates: Option<usize>,
+    /// `/tsp/geo`, set by `TSP_GEO_DEFAULT_GENERATIONS`.
+    geo: Option<usize>,
+}
+
+/// Parse the environment variable `name` or return `default` if it is not
+/// set or cannot be parsed.
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Parse the environment variable `name` if it is set and can be parsed.
+fn env_opt<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().and_then(|value| value.parse().ok())
+}
+
+/// All endpoints mounted on the server as `<method> <uri>`.
+struct Endpoints(Vec<String>);
+#[rocket::async_trait]
+impl<'r> request::FromRequest<'r> for Endpoints {
+    type Error = convert::Infallible;
+
+    async fn from_request(
+        request: &'r request::Request<'_>,
+    ) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(Endpoints(
+            request
+                .rocket()
+                .routes()
+                .map(|route| format!("{} {}", route.method, route.uri))
+                .collect(),
+        ))
+    }
+}
+
+/// Header that raises the log level of a single request to debug.
+const DEBUG_HEADER: &str = "X-Tsp-Debug";
+
+/// Where the request logs are written to. Rocket can manage another sink,
+/// e.g. a buffer in tests; without one the logs go to stderr.
+#[derive(Clone)]
+struct LogSink {
+    writer: Arc<Mutex<dyn io::Write + Send>>,
+}
+
+impl Default for LogSink {
+    fn default() -> LogSink {
+        LogSink {
+            writer: Arc::new(Mutex::new(io::stderr())),
+        }
+    }
+}
+
+/// Logger of a single request, which always writes warnings but only
+/// writes debug lines if the request asked for them with the
+/// `X-Tsp-Debug: true`-header.
+struct RequestLog {
+    debug: bool,
+    sink: LogSink,
+}
+
+impl RequestLog {
+    /// Build the logger from the value of the debug header, if any.
+    fn from_header(value: Option<&str>, sink: LogSink) -> RequestLog {
+        RequestLog {
+            debug: value.is_some_and(|value| value.trim().eq_ignore_ascii_case("true")),
+            sink,
+        }
+    }
+
+    /// Write `message` if this request logs at debug level.
+    fn debug(&self, message: impl std::fmt::Display) {
+        if self.debug {
+            self.write("debug", message);
+        }
+    }
+
+    /// Write `message` whatever level this request logs at.
+    fn warn(&self, message: impl std::fmt::Display) {
+        self.write("warn", message);
+    }
+
+    /// Write `message` to the sink, prefixed with its `level`.
+    fn write(&self, level: &str, message: impl std::fmt::Display) {
+        // A failing log must not fail the request.
+        let mut writer = self.sink.writer.lock().unwrap();
+        let _ = writeln!(writer, "[{}] {}", level, message);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> request::FromRequest<'r> for RequestLog {
+    type Error = convert::Infallible;
+
+    async fn from_request(
+        request: &'r request::Request<'_>,
+    ) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(RequestLog::from_header(
+            request.headers().get_one(DEBUG_HEADER),
+            request
+                .rocket()
+                .state::<LogSink>()
+                .cloned()
+                .unwrap_or_default(),
+        ))
+    }
+}
+
+/// Describe what this server supports, so that clients can configure
+/// themselves against it.
+#[get("/capabilities")]
+fn capabilities(config: &State<ApiConfig>, endpoints: Endpoints) -> json::Value {
+    json::json!({
+        "max_cities": config.max_cities,
+        "max_exact_n": config.max_exact_n,
+        "max_tour_distance": config.max_tour_distance,
+        "max_meta_points": config.max_meta_points,
+        "default_generations": config.default_generations,
+        "metrics": ["explicit", "euclidean_3d", "haversine"],
+        "endpoints": endpoints.0,
+        "formats": ["json", "csv", "gpx"],
+    })
+}
+
+/// Parameters of the genetic algorithm. They can be given with every
+/// request or stored under a name with the `/tsp/profiles`-endpoint.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct SolverParameters {
+    n_generations: Option<usize>,
+    n_routes: Option<usize>,
+    n_random_individuals_per_generation: Option<usize>,
+    top_n: Option<usize>,
+    crossover_rate: Option<f32>,
+    seed: Option<u64>,
+    init: Option<tsp_solver::InitStrategy>,
+    injection: Option<tsp_solver::Injection>,
+    tie_break: Option<tsp_solver::TieBreak>,
+    track_lineage: Option<bool>,
+    mutation_op: Option<tsp_solver::MutationOp>,
+    objective: Option<tsp_solver::Objective>,
+    track_exploration: Option<bool>,
+    patience: Option<usize>,
+    min_improvement: Option<f64>,
+    target_gap_percent: Option<f64>,
+    check_interval: Option<usize>,
+    two_opt: Option<bool>,
+    two_opt_max_passes: Option<usize>,
+}
+impl SolverParameters {
+    /// Use `n_generations` if neither the request nor its profile set it.
+    fn or_generations(self, n_generations: Option<usize>) -> SolverParameters {
+        SolverParameters {
+            n_generations: self.n_generations.or(n_generations),
+            ..self
+        }
+    }
+
+    /// Take all parameters that are set in `self` and fill the missing ones
+    /// from `fallback`.
+    fn or(self, fallback: &SolverParameters) -> SolverParameters {
+        SolverParameters {
+            n_generations: self.n_generations.or(fallback.n_generations),
+            n_routes: self.n_routes.or(fallback.n_routes),
+            n_random_individuals_per_generation: self
+                .n_random_individuals_per_generation
+                .or(fallback.n_random_individuals_per_generation),
+            top_n: self.top_n.or(fallback.top_n),
+            crossover_rate: self.crossover_rate.or(fallback.crossover_rate),
+            seed: self.seed.or(fallback.seed),
+            init: self.init.or(fallback.init),
+            injection: self.injection.or(fallback.injection),
+            tie_break: self.tie_break.or(fallback.tie_break),
+            track_lineage: self.track_lineage.or(fallback.track_lineage),
+            mutation_op: self.mutation_op.or(fallback.mutation_op),
+            objective: self.objective.or(fallback.objective),
+            track_exploration: self.track_exploration.or(fallback.track_exploration),
+            patience: self.patience.or(fallback.patience),
+            min_improvement: self.min_improvement.or(fallback.min_improvement),
+            target_gap_percent: self.target_gap_percent.or(fallback.target_gap_percent),
+            check_interval: self.check_interval.or(fallback.check_interval),
+            two_opt: self.two_opt.or(fallback.two_opt),
+            two_opt_max_passes: self.two_opt_max_passes.or(fallback.two_opt_max_passes),
+        }
+    }
+
+    /// The parameters that were effectively used for a run with `config`.
+    fn effective(&self, config: &tsp_solver::SolverConfig) -> SolverParameters {
+        SolverParameters {
+            n_generations: Some(config.n_generations),
+            n_routes: Some(config.n_routes),
+            n_random_individuals_per_generation: Some(config.n_random_individuals_per_generation),
+            top_n: Some(config.top_n),
+            crossover_rate: Some(config.crossover_rate),
+            seed: Some(config.seed),
+            init: Some(config.init),
+            injection: Some(config.injection),
+            tie_break: Some(config.tie_break),
+            track_lineage: Some(config.track_lineage),
+            mutation_op: Some(config.mutation_op),
+            objective: Some(config.objective),
+            track_exploration: Some(config.track_exploration),
+            patience: config.patience,
+            min_improvement: Some(config.min_improvement),
+            target_gap_percent: self.target_gap_percent,
+            check_interval: Some(config.check_interval),
+            two_opt: Some(self.two_opt == Some(true) || self.two_opt_max_passes.is_some()),
+            two_opt_max_passes: self.two_opt_max_passes,
+        }
+    }
+
+    /// Build the configuration of the genetic algorithm, using the solver's
+    /// defaults for all parameters that are not set.
+    fn to_config(&self) -> Result<tsp_solver::SolverConfig, String> {
+        let defaults = tsp_solver::SolverConfig::default();
+        let config = tsp_solver::SolverConfig {
+            n_generations: self.n_generations.ok_or_else(|| {
+                String::from("n_generations has to be given in the request or the profile.")
+            })?,
+            n_routes: self.n_routes.unwrap_or(defaults.n_routes),
+            n_random_individuals_per_generation: self
+                .n_random_individuals_per_generation
+                .unwrap_or(defaults.n_random_individuals_per_generation),
+            top_n: self.top_n.unwrap_or(defaults.top_n),
+            crossover_rate: self.crossover_rate.unwrap_or(defaults.crossover_rate),
+            seed: self.seed.unwrap_or(defaults.seed),
+            init: self.init.unwrap_or(defaults.init),
+            injection: self.injection.unwrap_or(defaults.injection),
+            tie_break: self.tie_break.unwrap_or(defaults.tie_break),
+            track_lineage: self.track_lineage.unwrap_or(defaults.track_lineage),
+            mutation_op: self.mutation_op.unwrap_or(defaults.mutation_op),
+            objective: self.objective.unwrap_or(defaults.objective),
+            track_exploration: self.track_exploration.unwrap_or(defaults.track_exploration),
+            patience: self.patience.or(defaults.patience),
+            min_improvement: self.min_improvement.unwrap_or(defaults.min_improvement),
+            // The target depends on the matrix, see `target_objective`.
+            target_objective: defaults.target_objective,
+            check_interval: self.check_interval.unwrap_or(defaults.check_interval),
+        };
+        tsp_solver::validate_config(&config)?;
+        if !(0.0..=1.0).contains(&config.crossover_rate) {
+            return Err(String::from("crossover_rate has to be in [0, 1]."));
+        }
+        if !(config.min_improvement.is_finite() && config.min_improvement >= 0.0) {
+            return Err(String::from("min_improvement has to be non-negative."));
+        }
+        if self
+            .target_gap_percent
+            .is_some_and(|gap| !(gap.is_finite() && gap >= 0.0))
+        {
+            return Err(String::from("target_gap_percent has to be non-negative."));
+        }
+        Ok(config)
+    }
+
+    /// The total distance at which a run on `distances` reaches the
+    /// `target_gap_percent` to the MST lower bound. The bound only holds for
+    /// the total distance on symmetric metric matrices.
+    fn target_objective(
+        &self,
+        distances: &[Vec<f64>],
+        objective: tsp_solver::Objective,
+    ) -> Result<Option<f64>, String> {
+        let gap = match self.target_gap_percent {
+            Some(gap) => gap,
+            None => return Ok(None),
+        };
+        if objective != tsp_solver::Objective::Distance {
+            return Err(String::from(
+                "target_gap_percent can only be used with the distance objective.",
+            ));
+        }
+        if !tsp_solver::is_symmetric(distances, 1e-9) || tsp_solver::metric_closure(distances).1 > 0
+        {
+            return Err(String::from(
+                "target_gap_percent can only be used on symmetric matrices that satisfy the triangle inequality.",
+            ));
+        }
+        Ok(Some(
+            tsp_solver::mst_lower_bound(distances) * (1.0 + gap / 100.0),
+        ))
+    }
+}
+
+/// Named parameter profiles registered with `/tsp/profiles`.
+#[derive(Default)]
+struct ProfileStore {
+    profiles: RwLock<HashMap<String, SolverParameters>>,
+}
+
+/// Random populations pre-generated with `/tsp/warm-pool`, by the number
+/// of cities and the number of routes. Every population is used by one
+/// unseeded solve only, which reports the seed of the population.
+#[derive(Default)]
+struct PopulationPool {
+    populations: Mutex<HashMap<(usize, usize), Vec<tsp_solver::SeededPopulation>>>,
+}
+impl PopulationPool {
+    /// Take a population of `n_routes` routes through `n_cities` cities out
+    /// of the pool, if there is one.
+    fn take(&self, n_cities: usize, n_routes: usize) -> Option<tsp_solver::SeededPopulation> {
+        self.populations
+            .lock()
+            .unwrap()
+            .get_mut(&(n_cities, n_routes))
+            .and_then(Vec::pop)
+    }
+}
+
 /// Data that is the input to the `/tsp`-endpoint.
 /// Mainly I need this because I cannot implement `Serialize`  or
 /// `Deserialize` for the foreign struct `DistanceMat`.
 #[derive(Serialize, Deserialize)]
 struct SolveTspData {
     distances: Vec<Vec<f64>>,
-    n_generations: usize,
+    labels: Option<Vec<String>>,
+    #[serde(default)]
+    auto_zero_diagonal: bool,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+    profile: Option<String>,
+    #[serde(flatten)]
+    options: ResponseOptions,
+}
+/// Options of the solve-endpoints that shape the response rather than the
+/// genetic algorithm.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct ResponseOptions {
+    #[serde(default)]
+    explain_edges: bool,
+    #[serde(default)]
+    canonical: bool,
+    #[serde(default)]
+    include: Vec<IncludeField>,
+    #[serde(default)]
+    verify_2opt: bool,
+    #[serde(default)]
+    sort: RouteOrder,
+    #[serde(default)]
+    output: Vec<RouteRepresentation>,
+    #[serde(default)]
+    spatial_summary: bool,
+    #[serde(default)]
+    compute_lower_bound: bool,
+    #[serde(default)]
+    rank_legs: bool,
+    plateau_fraction: Option<f64>,
+    #[serde(default)]
+    echo_input: bool,
+    #[serde(default)]
+    echo_matrix: bool,
+    max_meta_points: Option<usize>,
+    #[serde(default)]
+    group_by_distance: bool,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    report_normalized_distance: bool,
+    #[serde(default)]
+    report_crossings: bool,
+}
+/// Representations of the returned routes that can be requested with
+/// `output`. The indices are always returned as `route`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum RouteRepresentation {
+    Indices,
+    Labels,
+    Coordinates,
+    Edges,
+    PermutationMatrix,
+}
+/// What is known about the cities of a solve-request besides their
+/// distances.
+struct SolveInput<'a> {
+    units: Units,
+    labels: Option<&'a [String]>,
+    coordinates: Option<Vec<Vec<f64>>>,
+}
+impl SolveInput<'_> {
+    /// Build the representations of `route` that are requested in `output`.
+    fn represent(&self, route: &[usize], output: &[RouteRepresentation]) -> RouteRepresentations {
+        let requested = |representation| output.contains(&representation);
+        RouteRepresentations {
+            labels: self
+                .labels
+                .filter(|_| requested(RouteRepresentation::Labels))
+                .map(|labels| route.iter().map(|&city| labels[city].clone()).collect()),
+            coordinates: self
+                .coordinates
+                .as_ref()
+                .filter(|_| requested(RouteRepresentation::Coordinates))
+                .map(|coordinates| {
+                    route
+                        .iter()
+                        .map(|&city| coordinates[city].clone())
+                        .collect()
+                }),
+            edges: requested(RouteRepresentation::Edges).then(|| {
+                route
+                    .iter()
+                    .zip(route.iter().cycle().skip(1))
+                    .map(|(&from, &to)| [from, to])
+                    .collect()
+            }),
+            permutation_matrix: requested(RouteRepresentation::PermutationMatrix)
+                .then(|| tsp_solver::permutation_matrix(route)),
+        }
+    }
+}
+/// Order of the routes returned by the solve-endpoints.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum RouteOrder {
+    /// Shortest route first.
+    #[default]
+    Best,
+    /// Longest route first.
+    Worst,
+}
+/// Optional per-generation statistics and data that can be requested with
+/// `include` in the solve-endpoints.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum IncludeField {
+    History,
+    AvgHistory,
+    Diversity,
+    Schedule,
+    Population,
+}
+impl ResponseOptions {
+    /// Whether `field` was requested with `include`.
+    fn includes(&self, field: IncludeField) -> bool {
+        self.include.contains(&field)
+    }
+}
+/// A single leg of a route and its share of the total distance.
+#[derive(Serialize, Deserialize)]
+struct EdgeContribution {
+    from: usize,
+    to: usize,
+    distance: f64,
+    percent_of_total: f64,
+}
+/// A leg of a route together with its rank among all legs, the longest leg
+/// having rank 1.
+#[derive(Serialize, Deserialize)]
+struct RankedLeg {
+    rank: usize,
+    from: usize,
+    to: usize,
+    distance: f64,
 }
-/// Return type for the `/tsp`-enpoint.
+/// Return type for the `/tsp`-enpoint. `fitness` is always the total
+/// distance of the route, `bottleneck` its longest leg if the solve
+/// minimized that.
 #[derive(Serialize, Deserialize)]
 struct RouteWithFitness {
     route: Vec<usize>,
     fitness: f64,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    bottleneck: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    edge_contributions: Option<Vec<EdgeContribution>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ranked_legs: Option<Vec<RankedLeg>>,
+    #[serde(flatten)]
+    representations: RouteRepresentations,
+}
+/// Additional representations of a route that can be requested with
+/// `output`.
+#[derive(Serialize, Deserialize, Default)]
+struct RouteRepresentations {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    coordinates: Option<Vec<Vec<f64>>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    edges: Option<Vec<[usize; 2]>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    permutation_matrix: Option<Vec<Vec<u8>>>,
+}
+/// Information about how a solve was computed. `gap_percent` and
+/// `normalized_distance` refer to the total distance of the best route,
+/// whatever the `objective` was.
+#[derive(Serialize, Deserialize)]
+struct SolveMeta {
+    seed: u64,
+    objective: tsp_solver::Objective,
+    rng: String,
+    computation_ms: u64,
+    timings: PhaseTimings,
+    generations_run: usize,
+    stop_reason: tsp_solver::StopReason,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    two_opt_passes_run: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    history: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    avg_history: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    diversity: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    schedule: Option<Vec<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    population: Option<Vec<RouteWithFitness>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    is_2opt_optimal: Option<bool>,
+    plateaued: bool,
+    plateau_generation: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    lower_bound: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    gap_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    normalized_distance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    crossings: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    lineage: Option<Vec<tsp_solver::LineageStep>>,
+    distance_verified: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    warnings: Vec<String>,
+    population_from_pool: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    unique_tours_explored: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    distance_groups: Option<Vec<DistanceGroup>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    input: Option<InputEcho>,
+}
+/// The input of a solve-request as the server understood it, returned with
+/// `echo_input`. The matrix itself is only echoed with `echo_matrix`.
+#[derive(Serialize, Deserialize)]
+struct InputEcho {
+    n_cities: usize,
+    parameters: SolverParameters,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    distances: Option<Vec<Vec<f64>>>,
+}
+/// Routes of the final population that have the same distance, returned
+/// with `group_by_distance`.
+#[derive(Serialize, Deserialize)]
+struct DistanceGroup {
+    distance: f64,
+    count: usize,
+    /// The first `GROUP_MAX_REPRESENTATIVES` routes of the group.
+    routes: Vec<Vec<usize>>,
+}
+/// Most routes returned per `DistanceGroup`.
+const GROUP_MAX_REPRESENTATIVES: usize = 3;
+/// Distances are rounded to this many decimals before they are grouped,
+/// so that the same tour summed in another order lands in the same group.
+const GROUP_DECIMALS: i32 = 6;
+
+/// Group the `routes` into groups of equal rounded distance, from the
+/// shortest to the longest distance.
+fn distance_groups(distances: &[Vec<f64>], routes: &[route::Route]) -> Vec<DistanceGroup> {
+    let scale = 10f64.powi(GROUP_DECIMALS);
+    let mut rounded = routes
+        .iter()
+        .map(|route| {
+            let distance = tsp_solver::route_distance(distances, &route.indexes);
+            ((distance * scale).round() / scale, &route.indexes)
+        })
+        .collect::<Vec<(f64, &Vec<usize>)>>();
+    // The population is sorted by the objective, which need not be the
+    // distance.
+    rounded.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+    let mut groups: Vec<DistanceGroup> = Vec::new();
+    for (distance, route) in rounded {
+        match groups.last_mut() {
+            Some(group) if group.distance == distance => {
+                group.count += 1;
+                if group.routes.len() < GROUP_MAX_REPRESENTATIVES {
+                    group.routes.push(route.clone());
+                }
+            }
+            _ => groups.push(DistanceGroup {
+                distance,
+                count: 1,
+                routes: vec![route.clone()],
+            }),
+        }
+    }
+    groups
+}
+/// Unit of the distances of a solve-response.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Units {
+    /// Great-circle distances of the `/tsp/geo`-endpoint.
+    Km,
+    /// Distances of user-provided matrices or coordinates.
+    Arbitrary,
+}
+/// Location of the JSON schema of `SolveTspResponse`.
+const SOLVE_RESPONSE_SCHEMA: &str = "/schemas/solve-response.json";
+/// Wall-clock time spent in the phases of a solve-request, which add up to
+/// `computation_ms` up to rounding.
+#[derive(Serialize, Deserialize)]
+struct PhaseTimings {
+    validate_ms: u64,
+    build_ms: u64,
+    solve_ms: u64,
+    refine_ms: u64,
+}
+/// Return type of the solve-endpoints.
+#[derive(Serialize, Deserialize)]
+struct SolveTspResponse {
+    #[serde(rename = "$schema")]
+    schema: String,
+    units: Units,
+    routes: Vec<RouteWithFitness>,
+    meta: SolveMeta,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    spatial_summary: Option<SpatialSummary>,
+}
+/// Spatial extent of the cities of a coordinate input. Both use the axis
+/// order of the input points, so `bbox` is `[min_0, min_1, ..., max_0,
+/// max_1, ...]`.
+#[derive(Serialize, Deserialize)]
+struct SpatialSummary {
+    bbox: Vec<f64>,
+    centroid: Vec<f64>,
+}
+impl SpatialSummary {
+    /// Summarize the non-empty list of `points` of equal dimension.
+    fn of(points: &[Vec<f64>]) -> SpatialSummary {
+        let dimension = points[0].len();
+        let axis = |axis: usize| points.iter().map(move |point| point[axis]);
+        SpatialSummary {
+            bbox: (0..dimension)
+                .map(|dim| axis(dim).fold(f64::INFINITY, f64::min))
+                .chain((0..dimension).map(|dim| axis(dim).fold(f64::NEG_INFINITY, f64::max)))
+                .collect(),
+            centroid: (0..dimension)
+                .map(|dim| axis(dim).sum::<f64>() / points.len() as f64)
+                .collect(),
+        }
+    }
+}
+
+/// Compute how much every leg of the closed tour `route` contributes to its
+/// total distance.
+fn edge_contributions(distances: &[Vec<f64>], route: &[usize]) -> Vec<EdgeContribution> {
+    let legs = tsp_solver::route_legs(distances, route);
+    let total_distance: f64 = legs.iter().map(|(_, _, distance)| distance).sum();
+    legs.into_iter()
+        .map(|(from, to, distance)| EdgeContribution {
+            from,
+            to,
+            distance,
+            percent_of_total: if total_distance > 0.0 {
+                100.0 * distance / total_distance
+            } else {
+                0.0
+            },
+        })
+        .collect()
+}
+
+/// Rank the legs of the closed tour `route` from longest to shortest. Legs
+/// of equal distance keep their order along the route.
+fn ranked_legs(distances: &[Vec<f64>], route: &[usize]) -> Vec<RankedLeg> {
+    let mut legs = tsp_solver::route_legs(distances, route);
+    legs.sort_by(|(_, _, a), (_, _, b)| b.partial_cmp(a).unwrap_or(cmp::Ordering::Equal));
+    legs.into_iter()
+        .enumerate()
+        .map(|(index, (from, to, distance))| RankedLeg {
+            rank: index + 1,
+            from,
+            to,
+            distance,
+        })
+        .collect()
+}
+
+/// Combine the parameters of a request with the profile it references.
+/// Parameters given in the request take precedence over the profile.
+fn resolve_parameters(
+    parameters: &SolverParameters,
+    profile: Option<&str>,
+    profiles: &ProfileStore,
+) -> Result<SolverParameters, String> {
+    match profile {
+        None => Ok(parameters.clone()),
+        Some(name) => profiles
+            .profiles
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|profile| parameters.clone().or(profile))
+            .ok_or(format!("The profile {} does not exist.", name)),
+    }
+}
+
+/// Check that `distances` is a valid distance matrix of a size the server
+/// accepts.
+fn validate_distances(
+    distances: &[Vec<f64>],
+    config: &ApiConfig,
+) -> Result<(), status::BadRequest<json::Value>> {
+    tsp_solver::validate_distance_matrix(distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    if distances.len() > config.max_cities {
+        return Err(status::BadRequest(json::json!(format!(
+            "At most {} cities are supported, but {} were given.",
+            config.max_cities,
+            distances.len()
+        ))));
+    }
+    tsp_solver::validate_tour_magnitude(distances, config.max_tour_distance)
+        .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Down-sample a per-generation array of the solve meta to at most
+/// `max_points` values, if a limit is set.
+fn cap_points<T: Clone>(values: Vec<T>, max_points: Option<usize>) -> Vec<T> {
+    match max_points {
+        Some(max_points) => tsp_solver::downsample(&values, max_points),
+        None => values,
+    }
+}
+
+/// Error code of the response of a solve that panicked.
+const SOLVER_PANIC_CODE: &str = "solver_panic";
+
+/// Number of solves that panicked since the server started.
+static SOLVER_PANICS: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+/// Error of the solve-endpoints: the request is invalid, or the solver
+/// failed unexpectedly.
+#[derive(Responder)]
+enum SolveError {
+    /// The request is invalid.
+    BadRequest(status::BadRequest<json::Value>),
+    /// The solver panicked.
+    Failed(status::Custom<json::Value>),
+}
+
+impl From<status::BadRequest<json::Value>> for SolveError {
+    fn from(error: status::BadRequest<json::Value>) -> Self {
+        SolveError::BadRequest(error)
+    }
+}
+
+/// Run `solve` and turn a panic into a 500-response with a stable error
+/// code, so that a bug in the solver neither kills the worker thread nor
+/// leaks its message. `solve` may only borrow the inputs immutably, so
+/// nothing that is shared with other requests can be left half-updated.
+fn guard_solve<T>(solve: impl FnOnce() -> T + panic::UnwindSafe) -> Result<T, SolveError> {
+    panic::catch_unwind(solve).map_err(|_| {
+        SOLVER_PANICS.fetch_add(1, atomic::Ordering::Relaxed);
+        SolveError::Failed(status::Custom(
+            http::Status::InternalServerError,
+            json::json!({
+                "code": SOLVER_PANIC_CODE,
+                "message": FAILED_COMPUTATION_MESSAGE,
+            }),
+        ))
+    })
+}
+
+/// Report counters of the server since it started.
+#[get("/metrics")]
+fn metrics() -> json::Value {
+    json::json!({
+        "solver_panics": SOLVER_PANICS.load(atomic::Ordering::Relaxed),
+    })
+}
+
+/// Solve the traveling-salesman-problem defined by `distances` and build
+/// the response that is shared by all solve-endpoints, next to the best
+/// route, whichever order the response lists the routes in.
+fn solve_distances(
+    distances: &[Vec<f64>],
+    parameters: &SolverParameters,
+    options: &ResponseOptions,
+    input: &SolveInput,
+    config: &ApiConfig,
+    pool: &PopulationPool,
+    log: &RequestLog,
+) -> Result<(json::Value, Option<Vec<usize>>), SolveError> {
+    let start = time::Instant::now();
+    log.debug(format_args!(
+        "Solving a {}x{} distance matrix.",
+        distances.len(),
+        distances.first().map_or(0, Vec::len)
+    ));
+    validate_distances(distances, config)?;
+    if input
+        .labels
+        .is_some_and(|labels| labels.len() != distances.len())
+    {
+        return Err(
+            status::BadRequest(json::json!("labels has to contain one label per city.")).into(),
+        );
+    }
+    if options.output.contains(&RouteRepresentation::Labels) && input.labels.is_none() {
+        return Err(status::BadRequest(json::json!(
+            "Labels can only be returned if the request has labels."
+        ))
+        .into());
+    }
+    if options.output.contains(&RouteRepresentation::Coordinates) && input.coordinates.is_none() {
+        return Err(status::BadRequest(json::json!(
+            "Coordinates can only be returned for coordinate inputs."
+        ))
+        .into());
+    }
+    if options.spatial_summary && input.coordinates.is_none() {
+        return Err(status::BadRequest(json::json!(
+            "A spatial summary can only be computed for coordinate inputs."
+        ))
+        .into());
+    }
+    // Crossings are counted in the plane of the first two coordinates, in
+    // which all points have to lie.
+    let height = |point: &Vec<f64>| point.get(2).copied().unwrap_or(0.0);
+    let planar_points = input.coordinates.as_ref().and_then(|coordinates| {
+        coordinates
+            .iter()
+            .all(|point| height(point) == height(&coordinates[0]))
+            .then(|| {
+                coordinates
+                    .iter()
+                    .map(|point| [point[0], point[1]])
+                    .collect::<Vec<[f64; 2]>>()
+            })
+    });
+    if options.report_crossings && planar_points.is_none() {
+        return Err(status::BadRequest(json::json!(
+            "Crossings can only be counted for coordinate inputs in a plane."
+        ))
+        .into());
+    }
+    // Warnings are reported in the meta, or rejected in strict mode.
+    let warnings = tsp_solver::validation_warnings(distances, input.coordinates.as_deref());
+    if options.strict && !warnings.is_empty() {
+        return Err(status::BadRequest(json::json!(format!(
+            "The input has {} issue(s) in strict mode: {}",
+            warnings.len(),
+            warnings.join(" ")
+        )))
+        .into());
+    }
+    let mut solver_config = parameters
+        .to_config()
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    solver_config.target_objective = parameters
+        .target_objective(distances, solver_config.objective)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    // By default a run plateaued without improvement in the last fifth.
+    let plateau_fraction = options.plateau_fraction.unwrap_or(0.2);
+    if !(plateau_fraction > 0.0 && plateau_fraction <= 1.0) {
+        return Err(
+            status::BadRequest(json::json!("plateau_fraction has to be in (0, 1].")).into(),
+        );
+    }
+    let validated = time::Instant::now();
+    let distance_matrix = distance_mat::DistanceMat::new(distances.to_vec());
+    let built = time::Instant::now();
+    // Get a solution, starting from a pre-generated random population if
+    // there is one. The solve takes over the seed of the population, which
+    // reproduces it without the pool. A seeded solve generates its own
+    // population.
+    let pooled =
+        if solver_config.init == tsp_solver::InitStrategy::Random && parameters.seed.is_none() {
+            pool.take(distances.len(), solver_config.n_routes)
+        } else {
+            None
+        };
+    let population_from_pool = pooled.is_some();
+    if let Some(population) = &pooled {
+        solver_config.seed = population.seed;
+    }
+    let outcome = guard_solve(|| match pooled {
+        Some(population) => {
+            tsp_solver::solve_tsp_from_seeded_population(distances, &solver_config, population)
+        }
+        None => tsp_solver::solve_tsp_core(distances, &solver_config),
+    })?;
+    let solved = time::Instant::now();
+    let mut best_invdividuals = outcome.routes;
+    // Refine the routes with 2-opt if asked for, either to convergence or
+    // for a bounded number of passes.
+    let two_opt_passes_run =
+        if parameters.two_opt == Some(true) || parameters.two_opt_max_passes.is_some() {
+            let mut most_passes = 0;
+            for individual in best_invdividuals.iter_mut() {
+                let (refined, n_passes) = tsp_solver::two_opt(
+                    distances,
+                    &individual.indexes,
+                    parameters.two_opt_max_passes,
+                );
+                individual.indexes = refined;
+                most_passes = most_passes.max(n_passes);
+            }
+            tsp_solver::sort_routes(
+                &mut best_invdividuals,
+                distances,
+                solver_config.objective,
+                solver_config.tie_break,
+            );
+            Some(most_passes)
+        } else {
+            None
+        };
+
+    // Log duration.
+    let refined = time::Instant::now();
+    let timings = PhaseTimings {
+        validate_ms: tsp_solver::duration_to_ms(validated - start),
+        build_ms: tsp_solver::duration_to_ms(built - validated),
+        solve_ms: tsp_solver::duration_to_ms(solved - built),
+        refine_ms: tsp_solver::duration_to_ms(refined - solved),
+    };
+    let duration = tsp_solver::duration_to_ms(refined - start);
+    log.debug(format_args!("Computation took {} ms.", duration));
+    log.debug(format_args!(
+        "Phases took validate {} ms, build {} ms, solve {} ms, refine {} ms.",
+        timings.validate_ms, timings.build_ms, timings.solve_ms, timings.refine_ms
+    ));
+    log.debug(format_args!(
+        "Stopped after {} generations by {:?}.",
+        outcome.generations_run, outcome.stop_reason
+    ));
+    // Verify the best route after an optional refinement, so that
+    // `two_opt` can be used to fix routes that are not 2-opt optimal.
+    let is_2opt_optimal = if options.verify_2opt {
+        best_invdividuals
+            .first()
+            .map(|best| tsp_solver::is_two_opt_optimal(distances, &best.indexes))
+    } else {
+        None
+    };
+    let plateau_generation =
+        tsp_solver::plateau_generation(&outcome.best_history, plateau_fraction);
+    // The request can lift or tighten the limit of the server.
+    let max_meta_points = options.max_meta_points.or(config.max_meta_points);
+    // The bound is only computed on request, as it is quadratic in the
+    // number of cities.
+    let lower_bound = options
+        .compute_lower_bound
+        .then(|| tsp_solver::mst_lower_bound(distances));
+    let gap_percent = lower_bound
+        .zip(best_invdividuals.first())
+        .map(|(bound, best)| {
+            let best_distance = tsp_solver::route_distance(distances, &best.indexes);
+            if bound > 0.0 {
+                100.0 * (best_distance - bound) / bound
+            } else {
+                0.0
+            }
+        });
+    // The distance relative to that of an average random tour.
+    let normalized_distance = best_invdividuals
+        .first()
+        .filter(|_| options.report_normalized_distance)
+        .and_then(|best| {
+            tsp_solver::normalized_distance(
+                distances,
+                tsp_solver::route_distance(distances, &best.indexes),
+            )
+        });
+    let crossings = best_invdividuals
+        .first()
+        .zip(planar_points.as_deref())
+        .filter(|_| options.report_crossings)
+        .map(|(best, points)| tsp_solver::count_crossings(&best.indexes, points));
+    // Reversing a tour only keeps its distance on symmetric matrices.
+    let reversible = options.canonical && tsp_solver::is_symmetric(distances, 1e-9);
+    // The objective value is reported next to the distance if it differs.
+    let bottleneck = |route: &[usize]| {
+        (solver_config.objective == tsp_solver::Objective::Bottleneck)
+            .then(|| tsp_solver::bottleneck_distance(distances, route))
+    };
+    let mut best_individuals_with_fitness = best_invdividuals
+        .iter()
+        .enumerate()
+        .map(|(rank, individual)| {
+            let route = if options.canonical {
+                tsp_solver::canonical_route(&individual.indexes, reversible)
+            } else {
+                individual.indexes.clone()
+            };
+            RouteWithFitness {
+                representations: input.represent(&route, &options.output),
+                fitness: -individual.fitness(&distance_matrix),
+                bottleneck: bottleneck(&route),
+                // Only the best route is explained.
+                edge_contributions: if options.explain_edges && rank == 0 {
+                    Some(edge_contributions(distances, &individual.indexes))
+                } else {
+                    None
+                },
+                ranked_legs: if options.rank_legs && rank == 0 {
+                    Some(ranked_legs(distances, &route))
+                } else {
+                    None
+                },
+                route,
+            }
+        })
+        .collect::<Vec<RouteWithFitness>>();
+    // Guard against the fitness of the crate drifting from the distance of
+    // the returned route, e.g. by a change of sign.
+    let mut distance_verified = true;
+    for route in best_individuals_with_fitness.iter_mut() {
+        let recomputed = tsp_solver::route_distance(distances, &route.route);
+        if (recomputed - route.fitness).abs() > 1e-9 * recomputed.abs().max(1.0) {
+            log.warn(format_args!(
+                "The fitness {} of route {:?} does not match its distance {}.",
+                route.fitness, route.route, recomputed
+            ));
+            route.fitness = recomputed;
+            distance_verified = false;
+        }
+    }
+    // The routes are sorted best-first by the solver.
+    let best = best_individuals_with_fitness
+        .first()
+        .map(|route| route.route.clone());
+    if options.sort == RouteOrder::Worst {
+        best_individuals_with_fitness.reverse();
+    }
+    let response = json::json!(SolveTspResponse {
+        schema: String::from(SOLVE_RESPONSE_SCHEMA),
+        units: input.units,
+        routes: best_individuals_with_fitness,
+        spatial_summary: input
+            .coordinates
+            .as_deref()
+            .filter(|_| options.spatial_summary)
+            .map(SpatialSummary::of),
+        meta: SolveMeta {
+            seed: solver_config.seed,
+            objective: solver_config.objective,
+            rng: String::from(tsp_solver::RNG_ALGORITHM),
+            computation_ms: duration,
+            timings,
+            generations_run: outcome.generations_run,
+            stop_reason: outcome.stop_reason,
+            two_opt_passes_run,
+            history: options
+                .includes(IncludeField::History)
+                .then(|| cap_points(outcome.best_history, max_meta_points)),
+            avg_history: options
+                .includes(IncludeField::AvgHistory)
+                .then(|| cap_points(outcome.avg_history, max_meta_points)),
+            diversity: options
+                .includes(IncludeField::Diversity)
+                .then(|| cap_points(outcome.diversity_history, max_meta_points)),
+            schedule: options
+                .includes(IncludeField::Schedule)
+                .then(|| cap_points(outcome.mutation_schedule, max_meta_points)),
+            population: options.includes(IncludeField::Population).then(|| {
+                outcome
+                    .population
+                    .iter()
+                    .map(|individual| RouteWithFitness {
+                        route: individual.indexes.clone(),
+                        fitness: tsp_solver::route_distance(distances, &individual.indexes),
+                        bottleneck: bottleneck(&individual.indexes),
+                        edge_contributions: None,
+                        ranked_legs: None,
+                        representations: RouteRepresentations::default(),
+                    })
+                    .collect()
+            }),
+            is_2opt_optimal,
+            plateaued: plateau_generation.is_some(),
+            plateau_generation,
+            lower_bound,
+            gap_percent,
+            normalized_distance,
+            crossings,
+            lineage: outcome.lineage,
+            distance_verified,
+            warnings,
+            population_from_pool,
+            unique_tours_explored: outcome.unique_tours_explored,
+            distance_groups: options
+                .group_by_distance
+                .then(|| distance_groups(distances, &outcome.population)),
+            input: options.echo_input.then(|| InputEcho {
+                n_cities: distances.len(),
+                parameters: parameters.effective(&solver_config),
+                distances: options.echo_matrix.then(|| distances.to_vec()),
+            }),
+        },
+    });
+    Ok((response, best))
 }
 
 /// Main enpoint of the API that takes in a distance matrix and
 /// returns the optimal routes.
 #[post("/tsp", format = "json", data = "<input_parameters>")]
-fn solve_tsp(input_parameters: json::Json<SolveTspData>) -> json::Value {
+fn solve_tsp(
+    input_parameters: json::Json<SolveTspData>,
+    profiles: &State<ProfileStore>,
+    config: &State<ApiConfig>,
+    pool: &State<PopulationPool>,
+    log: RequestLog,
+) -> Result<json::Value, SolveError> {
     let input_parameters: SolveTspData = input_parameters.into_inner();
-    // Load in the test matrix.
-    let distances = distance_mat::DistanceMat::new(input_parameters.distances);
-    // log distance matrix provided.
-    println!("{:?}", distances);
-    // Get a solution
-    let before = time::Instant::now();
-    let best_invdividuals =
-        tsp_solver::solve_tsp(&distances, input_parameters.n_generations, 30, 10, 3);
+    let parameters = resolve_parameters(
+        &input_parameters.parameters,
+        input_parameters.profile.as_deref(),
+        profiles,
+    )
+    .map(|parameters| parameters.or_generations(config.default_generations.matrix))
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let distances = if input_parameters.auto_zero_diagonal {
+        tsp_solver::zero_diagonal(&input_parameters.distances)
+    } else {
+        input_parameters.distances
+    };
+    solve_distances(
+        &distances,
+        &parameters,
+        &input_parameters.options,
+        &SolveInput {
+            units: Units::Arbitrary,
+            labels: input_parameters.labels.as_deref(),
+            coordinates: None,
+        },
+        config,
+        pool,
+        &log,
+    )
+    .map(|(response, _)| response)
+}
 
-    // Log duration.
-    let duration = tsp_solver::duration_to_ms(before.elapsed());
-    println!("Computation took {}", duration);
-    let best_individuals_with_fitness = best_invdividuals
-        .iter()
-        .map(|individual| RouteWithFitness {
-            route: individual.indexes.clone(),
-            fitness: -individual.fitness(&distances),
+/// Solve the reverse problem of an asymmetric matrix: the matrix is
+/// transposed before it is solved, so the distance from `a` to `b` is the
+/// one of the request from `b` to `a`. The routes and fitnesses refer to the
+/// transposed matrix.
+#[post("/tsp/reverse", format = "json", data = "<input_parameters>")]
+fn solve_reverse(
+    input_parameters: json::Json<SolveTspData>,
+    profiles: &State<ProfileStore>,
+    config: &State<ApiConfig>,
+    pool: &State<PopulationPool>,
+    log: RequestLog,
+) -> Result<json::Value, SolveError> {
+    let input_parameters: SolveTspData = input_parameters.into_inner();
+    let distances = if input_parameters.auto_zero_diagonal {
+        tsp_solver::zero_diagonal(&input_parameters.distances)
+    } else {
+        input_parameters.distances
+    };
+    tsp_solver::validate_distance_matrix(&distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let parameters = resolve_parameters(
+        &input_parameters.parameters,
+        input_parameters.profile.as_deref(),
+        profiles,
+    )
+    .map(|parameters| parameters.or_generations(config.default_generations.matrix))
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    solve_distances(
+        &tsp_solver::transpose(&distances),
+        &parameters,
+        &input_parameters.options,
+        &SolveInput {
+            units: Units::Arbitrary,
+            labels: input_parameters.labels.as_deref(),
+            coordinates: None,
+        },
+        config,
+        pool,
+        &log,
+    )
+    .map(|(response, _)| response)
+}
+
+/// Most populations `/tsp/warm-pool` creates per request.
+const WARM_POOL_MAX_COUNT: usize = 100;
+/// Most routes of a population created by `/tsp/warm-pool`.
+const WARM_POOL_MAX_ROUTES: usize = 1000;
+/// Most populations pooled for one number of cities and routes.
+const WARM_POOL_MAX_POOLED: usize = 100;
+/// Most populations pooled over all numbers of cities and routes.
+const WARM_POOL_MAX_TOTAL: usize = 1000;
+
+/// Input to the `/tsp/warm-pool`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct WarmPoolData {
+    n: usize,
+    n_routes: usize,
+    count: usize,
+}
+
+/// Pre-generate `count` random populations of `n_routes` routes through `n`
+/// cities, each with its own random seed. Unseeded solves of `n` cities
+/// with `n_routes` routes and the random initialization use one of them
+/// instead of generating their own and report its seed, so that sending
+/// that seed reproduces them. Seeded solves never use the pool. The pool holds at most `WARM_POOL_MAX_POOLED` populations of
+/// one size and `WARM_POOL_MAX_TOTAL` populations overall. Returns how many
+/// populations of that size are pooled.
+#[post("/tsp/warm-pool", format = "json", data = "<input_parameters>")]
+fn warm_pool(
+    input_parameters: json::Json<WarmPoolData>,
+    pool: &State<PopulationPool>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let WarmPoolData { n, n_routes, count } = input_parameters.into_inner();
+    let within_bounds = (1..=config.max_cities).contains(&n)
+        && (1..=WARM_POOL_MAX_ROUTES).contains(&n_routes)
+        && (1..=WARM_POOL_MAX_COUNT).contains(&count);
+    if !within_bounds {
+        return Err(status::BadRequest(json::json!(format!(
+            "n has to be in [1, {}], n_routes in [1, {}] and count in [1, {}].",
+            config.max_cities, WARM_POOL_MAX_ROUTES, WARM_POOL_MAX_COUNT
+        ))));
+    }
+    let populations = (0..count)
+        .map(|_| tsp_solver::SeededPopulation::new(tsp_solver::random_seed(), n_routes, n))
+        .collect::<Vec<_>>();
+    let mut pooled = pool.populations.lock().unwrap();
+    let total = pooled.values().map(Vec::len).sum::<usize>();
+    let pooled = pooled.entry((n, n_routes)).or_default();
+    if pooled.len() + count > WARM_POOL_MAX_POOLED || total + count > WARM_POOL_MAX_TOTAL {
+        return Err(status::BadRequest(json::json!(format!(
+            "The pool holds at most {} populations of one size and {} overall.",
+            WARM_POOL_MAX_POOLED, WARM_POOL_MAX_TOTAL
+        ))));
+    }
+    pooled.extend(populations);
+    Ok(json::json!({
+        "n": n,
+        "n_routes": n_routes,
+        "pooled": pooled.len(),
+    }))
+}
+
+/// Input to the `/tsp/coordinates/3d`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct SolveCoordinates3dData {
+    points: Vec<[f64; 3]>,
+    labels: Option<Vec<String>>,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+    profile: Option<String>,
+    #[serde(flatten)]
+    options: ResponseOptions,
+}
+
+/// Solve the traveling-salesman-problem for points in 3D space, using the
+/// euclidean distance between them.
+#[post("/tsp/coordinates/3d", format = "json", data = "<input_parameters>")]
+fn solve_coordinates_3d(
+    input_parameters: json::Json<SolveCoordinates3dData>,
+    profiles: &State<ProfileStore>,
+    config: &State<ApiConfig>,
+    pool: &State<PopulationPool>,
+    log: RequestLog,
+) -> Result<json::Value, SolveError> {
+    let input_parameters: SolveCoordinates3dData = input_parameters.into_inner();
+    let parameters = resolve_parameters(
+        &input_parameters.parameters,
+        input_parameters.profile.as_deref(),
+        profiles,
+    )
+    .map(|parameters| parameters.or_generations(config.default_generations.coordinates))
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    solve_distances(
+        &tsp_solver::euclidean_matrix_3d(&input_parameters.points),
+        &parameters,
+        &input_parameters.options,
+        &SolveInput {
+            units: Units::Arbitrary,
+            labels: input_parameters.labels.as_deref(),
+            coordinates: Some(
+                input_parameters
+                    .points
+                    .iter()
+                    .map(|point| point.to_vec())
+                    .collect(),
+            ),
+        },
+        config,
+        pool,
+        &log,
+    )
+    .map(|(response, _)| response)
+}
+
+/// Input to the `/tsp/geo`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct SolveGeoData {
+    points: Vec<[f64; 2]>,
+    labels: Option<Vec<String>>,
+    #[serde(default)]
+    itinerary: bool,
+    #[serde(default)]
+    report_metrics: Vec<ReportMetric>,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+    profile: Option<String>,
+    #[serde(flatten)]
+    options: ResponseOptions,
+}
+
+/// Solve the traveling-salesman-problem for `[latitude, longitude]`-points
+/// on earth, using the great-circle distance in kilometers between them.
+#[post("/tsp/geo", format = "json", data = "<input_parameters>")]
+fn solve_geo(
+    input_parameters: json::Json<SolveGeoData>,
+    profiles: &State<ProfileStore>,
+    config: &State<ApiConfig>,
+    pool: &State<PopulationPool>,
+    accept: Option<&http::Accept>,
+    log: RequestLog,
+) -> Result<GeoResponse, SolveError> {
+    let input_parameters: SolveGeoData = input_parameters.into_inner();
+    let parameters = resolve_parameters(
+        &input_parameters.parameters,
+        input_parameters.profile.as_deref(),
+        profiles,
+    )
+    .map(|parameters| parameters.or_generations(config.default_generations.geo))
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let distances = tsp_solver::haversine_matrix(&input_parameters.points)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let labels = input_parameters.labels.as_deref();
+    if input_parameters.itinerary && labels.is_none() {
+        return Err(
+            status::BadRequest(json::json!("An itinerary can only be built with labels.")).into(),
+        );
+    }
+    let (mut response, best) = solve_distances(
+        &distances,
+        &parameters,
+        &input_parameters.options,
+        &SolveInput {
+            units: Units::Km,
+            labels,
+            coordinates: Some(
+                input_parameters
+                    .points
+                    .iter()
+                    .map(|point| point.to_vec())
+                    .collect(),
+            ),
+        },
+        config,
+        pool,
+        &log,
+    )?;
+    let best = best.ok_or_else(|| status::BadRequest(json::json!("No route was found.")))?;
+    let gpx = http::MediaType::new("application", "gpx+xml");
+    if accept.is_some_and(|accept| accept.media_types().any(|media_type| *media_type == gpx)) {
+        return Ok(GeoResponse::Gpx((
+            http::ContentType(gpx),
+            tsp_solver::route_to_gpx(&best, &input_parameters.points),
+        )));
+    }
+    if let (true, Some(labels)) = (input_parameters.itinerary, labels) {
+        response["itinerary"] = json::json!(itinerary(&distances, &best, labels));
+    }
+    if !input_parameters.report_metrics.is_empty() {
+        let metric_distances = input_parameters
+            .report_metrics
+            .iter()
+            .map(|&metric| {
+                let distance = match metric {
+                    ReportMetric::Haversine => tsp_solver::route_distance(&distances, &best),
+                    ReportMetric::Euclidean => tsp_solver::route_distance(
+                        &tsp_solver::euclidean_matrix_3d(&tsp_solver::equirectangular_points(
+                            &input_parameters.points,
+                        )),
+                        &best,
+                    ),
+                };
+                (metric, distance)
+            })
+            .collect::<HashMap<ReportMetric, f64>>();
+        response["metric_distances"] = json::json!(metric_distances);
+    }
+    Ok(GeoResponse::Json(response))
+}
+
+/// Metric the best route of `/tsp/geo` can additionally be scored with.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+enum ReportMetric {
+    /// The great-circle distance in kilometers the route was solved with.
+    Haversine,
+    /// The euclidean distance in kilometers between the points projected
+    /// to the plane with the equirectangular projection.
+    Euclidean,
+}
+
+/// Return type of the `/tsp/geo`-endpoint: the solve-response, or the best
+/// route as a GPX track if the request accepts `application/gpx+xml`.
+#[derive(Responder)]
+enum GeoResponse {
+    /// The solve-response, as returned by all solve-endpoints.
+    Json(json::Value),
+    /// The GPX track of the best route.
+    Gpx((http::ContentType, String)),
+}
+
+/// Describe the closed tour `route` as one line per stop, starting and
+/// ending at its first city, with the distance of every leg in kilometers.
+fn itinerary(distances: &[Vec<f64>], route: &[usize], labels: &[String]) -> Vec<String> {
+    let legs = tsp_solver::route_legs(distances, route);
+    let n_legs = legs.len();
+    route
+        .first()
+        .map(|&start| format!("Start at {}", labels[start]))
+        .into_iter()
+        .chain(
+            legs.into_iter()
+                .enumerate()
+                .map(|(leg, (_, to, distance))| {
+                    let verb = if leg + 1 == n_legs {
+                        "Return to"
+                    } else {
+                        "Go to"
+                    };
+                    format!("{} {} ({:.0} km)", verb, labels[to], distance)
+                }),
+        )
+        .collect()
+}
+
+/// Return the JSON schema that describes the responses of the
+/// solve-endpoints.
+#[get("/schemas/solve-response.json")]
+fn solve_response_schema() -> json::Value {
+    json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "SolveTspResponse",
+        "type": "object",
+        "required": ["units", "routes", "meta"],
+        "properties": {
+            "$schema": {"type": "string"},
+            "units": {"enum": ["km", "arbitrary"]},
+            "routes": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["route", "fitness"],
+                    "properties": {
+                        "route": {"type": "array", "items": {"type": "integer", "minimum": 0}},
+                        "fitness": {"type": "number"},
+                        "edge_contributions": {"type": "array"}
+                    }
+                }
+            },
+            "meta": {
+                "type": "object",
+                "required": ["seed", "rng", "computation_ms", "generations_run"],
+                "properties": {
+                    "seed": {"type": "integer"},
+                    "rng": {"type": "string"},
+                    "computation_ms": {"type": "integer"},
+                    "generations_run": {"type": "integer"}
+                }
+            }
+        }
+    })
+}
+
+/// Input to the `/tsp/profiles`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct ProfileData {
+    name: String,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+}
+
+/// Register a named parameter profile that can be referenced with
+/// `{"profile": <name>}` in `/tsp`. Registering an existing name replaces
+/// the stored profile.
+#[post("/tsp/profiles", format = "json", data = "<input_parameters>")]
+fn register_profile(
+    input_parameters: json::Json<ProfileData>,
+    profiles: &State<ProfileStore>,
+) -> json::Value {
+    let input_parameters: ProfileData = input_parameters.into_inner();
+    let name = input_parameters.name.clone();
+    profiles
+        .profiles
+        .write()
+        .unwrap()
+        .insert(input_parameters.name, input_parameters.parameters);
+    json::json!(name)
+}
+
+/// Input to the `/tsp/matrix/submatrix`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct SubmatrixData {
+    distances: Vec<Vec<f64>>,
+    indices: Vec<usize>,
+}
+
+/// Return the sub-matrix induced by the given city indices, e.g. to solve
+/// a what-if scenario on a subset of the cities.
+#[post("/tsp/matrix/submatrix", format = "json", data = "<input_parameters>")]
+fn matrix_submatrix(
+    input_parameters: json::Json<SubmatrixData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let input_parameters: SubmatrixData = input_parameters.into_inner();
+    tsp_solver::validate_distance_matrix(&input_parameters.distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    tsp_solver::submatrix(&input_parameters.distances, &input_parameters.indices)
+        .map(|sub| json::json!(sub))
+        .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Input to the `/tsp/matrix/complete`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct CompleteMatrixData {
+    distances: Vec<Vec<f64>>,
+    sentinel: f64,
+    strategy: tsp_solver::FillStrategy,
+}
+
+/// Fill the cells of a distance matrix that are marked as missing with a
+/// sentinel value.
+#[post("/tsp/matrix/complete", format = "json", data = "<input_parameters>")]
+fn matrix_complete(
+    input_parameters: json::Json<CompleteMatrixData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let input_parameters: CompleteMatrixData = input_parameters.into_inner();
+    // The missing cells are not valid distances yet, so only the shape and
+    // the size can be checked up front.
+    tsp_solver::validate_matrix_shape(&input_parameters.distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    if input_parameters.distances.len() > config.max_cities {
+        return Err(status::BadRequest(json::json!(format!(
+            "At most {} cities are supported, but {} were given.",
+            config.max_cities,
+            input_parameters.distances.len()
+        ))));
+    }
+    tsp_solver::complete_missing(
+        &input_parameters.distances,
+        input_parameters.sentinel,
+        input_parameters.strategy,
+    )
+    .map(|completed| json::json!(completed))
+    .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Input to the `/tsp/route/two-opt-step`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct TwoOptStepData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+    i: Option<usize>,
+    j: Option<usize>,
+}
+
+/// Return type of the `/tsp/route/two-opt-step`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct TwoOptStepResult {
+    route: Vec<usize>,
+    distance: f64,
+    reversed: Option<[usize; 2]>,
+}
+
+/// Apply a single 2-opt move to a route. If `i` and `j` are given, the
+/// segment `route[i..=j]` is reversed, otherwise the best improving move is
+/// applied. If no move improves the route it is returned unchanged.
+#[post(
+    "/tsp/route/two-opt-step",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn route_two_opt_step(
+    input_parameters: json::Json<TwoOptStepData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let input_parameters: TwoOptStepData = input_parameters.into_inner();
+    let distances = &input_parameters.distances;
+    let route = &input_parameters.route;
+    tsp_solver::validate_distance_matrix(distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    tsp_solver::validate_route(route, distances.len())
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let reversed = match (input_parameters.i, input_parameters.j) {
+        (Some(i), Some(j)) if i < j && j < route.len() => Some((i, j)),
+        (None, None) => tsp_solver::best_two_opt_move(distances, route).map(|(i, j, _)| (i, j)),
+        _ => {
+            return Err(status::BadRequest(json::json!(
+                "i and j have to be given together with i < j < the number of cities."
+            )))
+        }
+    };
+    let new_route = match reversed {
+        Some((i, j)) => tsp_solver::two_opt_move(route, i, j),
+        None => route.clone(),
+    };
+    Ok(json::json!(TwoOptStepResult {
+        distance: tsp_solver::route_distance(distances, &new_route),
+        route: new_route,
+        reversed: reversed.map(|(i, j)| [i, j]),
+    }))
+}
+
+/// Input to the `/tsp/route/repair`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct RepairRouteData {
+    route: Vec<usize>,
+    n: usize,
+}
+
+/// Return type of the `/tsp/route/repair`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct RepairRouteResult {
+    route: Vec<usize>,
+    edits: Vec<tsp_solver::RouteEdit>,
+}
+
+/// Repair a route that contains duplicate, unknown or missing cities into a
+/// valid permutation of `n` cities and list the edits that were made.
+#[post("/tsp/route/repair", format = "json", data = "<input_parameters>")]
+fn route_repair(input_parameters: json::Json<RepairRouteData>) -> json::Value {
+    let (route, edits) = tsp_solver::repair_route(&input_parameters.route, input_parameters.n);
+    json::json!(RepairRouteResult { route, edits })
+}
+
+/// Input to all endpoints that only transform a distance matrix.
+#[derive(Serialize, Deserialize)]
+struct MatrixData {
+    distances: Vec<Vec<f64>>,
+}
+
+/// Return the metric closure of a distance matrix, which satisfies the
+/// triangle inequality, together with the number of cells that changed.
+#[post(
+    "/tsp/matrix/metric-closure",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_metric_closure(
+    input_parameters: json::Json<MatrixData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let (closure, n_changed) = tsp_solver::metric_closure(distances);
+    Ok(json::json!({"distances": closure, "changed_cells": n_changed}))
+}
+
+/// Most short solves `/tsp/bracket` runs per request.
+const BRACKET_MAX_RESTARTS: usize = 20;
+
+/// Input to the `/tsp/bracket`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct BracketData {
+    distances: Vec<Vec<f64>>,
+    n_restarts: Option<usize>,
+    n_generations: Option<usize>,
+    seed: Option<u64>,
+}
+
+/// Bracket the optimal tour length without a full optimization: the upper
+/// end is the best tour out of a few short solves and the nearest-neighbor
+/// heuristic, the lower end is the minimum-spanning-tree bound.
+/// `estimated_gap` is the gap between both in percent of the lower bound.
+#[post("/tsp/bracket", format = "json", data = "<input_parameters>")]
+fn bracket(
+    input_parameters: json::Json<BracketData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, SolveError> {
+    let input_parameters: BracketData = input_parameters.into_inner();
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let n_restarts = input_parameters.n_restarts.unwrap_or(3);
+    if n_restarts > BRACKET_MAX_RESTARTS {
+        return Err(status::BadRequest(json::json!(format!(
+            "n_restarts has to be at most {}.",
+            BRACKET_MAX_RESTARTS
+        )))
+        .into());
+    }
+    let seed = input_parameters
+        .seed
+        .unwrap_or_else(tsp_solver::random_seed);
+    let solver_config = SolverParameters {
+        n_generations: Some(input_parameters.n_generations.unwrap_or(100)),
+        top_n: Some(1),
+        seed: Some(seed),
+        ..SolverParameters::default()
+    }
+    .to_config()
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let nearest_neighbor = (0..distances.len())
+        .map(|start| {
+            tsp_solver::route_distance(
+                distances,
+                &tsp_solver::nearest_neighbor_route(distances, start),
+            )
         })
-        .collect::<Vec<RouteWithFitness>>();
-    json::json!(best_individuals_with_fitness)
+        .fold(f64::INFINITY, f64::min);
+    let best_found = guard_solve(|| {
+        (0..n_restarts as u64)
+            .flat_map(|restart| {
+                tsp_solver::solve_tsp_core(
+                    distances,
+                    &tsp_solver::SolverConfig {
+                        seed: seed.wrapping_add(restart),
+                        ..solver_config.clone()
+                    },
+                )
+                .routes
+            })
+            .map(|route| tsp_solver::route_distance(distances, &route.indexes))
+            .fold(nearest_neighbor, f64::min)
+    })?;
+    let lower_bound = tsp_solver::mst_lower_bound(distances);
+    Ok(json::json!({
+        "best_found": best_found,
+        "lower_bound": lower_bound,
+        "estimated_gap": if lower_bound > 0.0 {
+            100.0 * (best_found - lower_bound) / lower_bound
+        } else {
+            0.0
+        },
+        "seed": seed,
+    }))
 }
-/// If an enpoint cannot be found, return "Not found!"
-#[catch(404)]
-fn not_found() -> json::Value {
-    json::json!("Not found!")
+
+/// Largest number of random tours `/tsp/random-baseline` draws.
+const RANDOM_BASELINE_MAX_SAMPLES: usize = 100_000;
+
+/// Input to the `/tsp/random-baseline`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct RandomBaselineData {
+    distances: Vec<Vec<f64>>,
+    samples: usize,
+    seed: Option<u64>,
 }
 
-/// If an internal server error happens, return "Your computation could not be done."
-#[catch(500)]
-fn failed_computation() -> json::Value {
-    json::json!("Your computation could not be done.")
+/// The mean, standard deviation, minimum and maximum distance of `samples`
+/// random tours, as a baseline for the tours found by the solver.
+#[post("/tsp/random-baseline", format = "json", data = "<input_parameters>")]
+fn random_baseline(
+    input_parameters: json::Json<RandomBaselineData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    if !(1..=RANDOM_BASELINE_MAX_SAMPLES).contains(&input_parameters.samples) {
+        return Err(status::BadRequest(json::json!(format!(
+            "samples has to be between 1 and {}.",
+            RANDOM_BASELINE_MAX_SAMPLES
+        ))));
+    }
+    let seed = input_parameters
+        .seed
+        .unwrap_or_else(tsp_solver::random_seed);
+    let mut rng = tsp_solver::SolverRng::seed_from_u64(seed);
+    let stats = tsp_solver::random_baseline(distances, input_parameters.samples, &mut rng);
+    Ok(json::json!({
+        "mean": stats.mean,
+        "std": stats.std,
+        "min": stats.min,
+        "max": stats.max,
+        "seed": seed,
+    }))
 }
 
-/// Build Rocket API.
-#[launch]
-fn rocket() -> _ {
-    rocket::build()
-        .mount("/", routes![liveness_probe, solve_tsp])
-        .register("/", catchers![not_found, failed_computation])
+/// Input to the `/tsp/matrix/hash`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct MatrixHashData {
+    distances: Vec<Vec<f64>>,
+    config: Option<SolverParameters>,
 }
 
-#[cfg(test)]
-mod test {
-    use super::rocket;
-    use super::*;
-    use rocket::http;
-    use rocket::local::blocking;
-    use serde_json;
+/// Return a stable hash of a distance matrix and an optional solver
+/// configuration, so that clients can deduplicate their requests.
+#[post("/tsp/matrix/hash", format = "json", data = "<input_parameters>")]
+fn matrix_hash(input_parameters: json::Json<MatrixHashData>) -> json::Value {
+    // The fields of `SolverParameters` are always serialized in the same
+    // order, which makes the JSON a canonical form of the configuration.
+    let config = input_parameters
+        .config
+        .as_ref()
+        .map(|config| json::to_string(config).unwrap());
+    json::json!({
+        "hash": tsp_solver::matrix_hash(&input_parameters.distances, config.as_deref())
+    })
+}
 
+/// Input to the `/tsp/route/hash`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct RouteHashData {
+    route: Vec<usize>,
+    /// Set for routes of asymmetric problems, on which the reverse of a
+    /// tour is a different tour.
+    #[serde(default)]
+    asymmetric: bool,
+}
+
+/// Return a stable hash of the canonical form of a route, so that clients
+/// can deduplicate tours regardless of their start and direction.
+#[post("/tsp/route/hash", format = "json", data = "<input_parameters>")]
+fn route_hash(
+    input_parameters: json::Json<RouteHashData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let route = &input_parameters.route;
+    tsp_solver::validate_route(route, route.len())
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "hash": tsp_solver::route_hash(route, !input_parameters.asymmetric),
+        "canonical": tsp_solver::canonical_route(route, !input_parameters.asymmetric),
+    }))
+}
+
+/// Input to the `/tsp/matrix/from-durations`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct FromDurationsData {
+    durations: Vec<Vec<f64>>,
+    speed: f64,
+}
+
+/// Convert a matrix of travel durations into a distance matrix by
+/// multiplying every duration with a constant speed.
+#[post(
+    "/tsp/matrix/from-durations",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_from_durations(
+    input_parameters: json::Json<FromDurationsData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    tsp_solver::durations_to_distances(&input_parameters.durations, input_parameters.speed)
+        .map(|distances| json::json!(distances))
+        .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Input to the `/tsp/matrix/from-adjacency`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct FromAdjacencyData {
+    n: usize,
+    edges: Vec<[usize; 2]>,
+}
+
+/// Build a distance matrix from an unweighted, undirected graph in which the
+/// distance between two cities is the number of hops between them. Cities
+/// that cannot reach each other have a distance of `null` and are listed in
+/// `unreachable`.
+#[post(
+    "/tsp/matrix/from-adjacency",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_from_adjacency(
+    input_parameters: json::Json<FromAdjacencyData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let hops = tsp_solver::hop_distances(input_parameters.n, &input_parameters.edges)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let mut unreachable = Vec::new();
+    let distances = hops
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(to, &distance)| {
+                    if distance.is_finite() {
+                        Some(distance)
+                    } else {
+                        unreachable.push([from, to]);
+                        None
+                    }
+                })
+                .collect::<Vec<Option<f64>>>()
+        })
+        .collect::<Vec<Vec<Option<f64>>>>();
+    Ok(json::json!({"distances": distances, "unreachable": unreachable}))
+}
+
+/// Input to the `/tsp/matrix/cluster`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct ClusterData {
+    distances: Vec<Vec<f64>>,
+    k: usize,
+}
+
+/// Partition the cities of a distance matrix into `k` clusters with
+/// k-medoids, e.g. to solve large instances cluster by cluster.
+#[post("/tsp/matrix/cluster", format = "json", data = "<input_parameters>")]
+fn matrix_cluster(
+    input_parameters: json::Json<ClusterData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    validate_distances(&input_parameters.distances, config)?;
+    tsp_solver::k_medoids(&input_parameters.distances, input_parameters.k)
+        .map(|clustering| json::json!(clustering))
+        .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Input to the `/tsp/cluster-solve`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct ClusterSolveData {
+    distances: Vec<Vec<f64>>,
+    k: usize,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+    profile: Option<String>,
+}
+
+/// Solve a large instance by clustering the cities into `k` clusters,
+/// solving every cluster on its own and stitching the sub-tours together.
+#[post("/tsp/cluster-solve", format = "json", data = "<input_parameters>")]
+fn cluster_solve(
+    input_parameters: json::Json<ClusterSolveData>,
+    profiles: &State<ProfileStore>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, SolveError> {
+    let input_parameters: ClusterSolveData = input_parameters.into_inner();
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let solver_config = resolve_parameters(
+        &input_parameters.parameters,
+        input_parameters.profile.as_deref(),
+        profiles,
+    )
+    .and_then(|parameters| parameters.to_config())
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let route =
+        guard_solve(|| tsp_solver::cluster_solve(distances, input_parameters.k, &solver_config))?
+            .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "distance": tsp_solver::route_distance(distances, &route),
+        "route": route,
+        "seed": solver_config.seed,
+    }))
+}
+
+/// Input to the `/tsp/route/insert-city`- and `/tsp/route/remove-city`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct RouteCityData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+    city: usize,
+}
+
+/// Insert a new city into a route at the position that adds the least
+/// distance, and return the new route together with the added distance.
+#[post("/tsp/route/insert-city", format = "json", data = "<input_parameters>")]
+fn route_insert_city(
+    input_parameters: json::Json<RouteCityData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    tsp_solver::validate_distance_matrix(&input_parameters.distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let (route, position, added) = tsp_solver::cheapest_insertion(
+        &input_parameters.distances,
+        &input_parameters.route,
+        input_parameters.city,
+    )
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "distance": tsp_solver::route_distance(&input_parameters.distances, &route),
+        "route": route,
+        "position": position,
+        "delta": added,
+    }))
+}
+
+/// Input to the `/tsp/route/insertion-costs`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct InsertionCostsData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+}
+
+/// The cheapest insertion of a city into a route, returned by
+/// `/tsp/route/insertion-costs`.
+#[derive(Serialize, Deserialize)]
+struct InsertionCost {
+    city: usize,
+    best_position: usize,
+    added_distance: f64,
+}
+
+/// Compute the cheapest insertion of every city that a route does not
+/// visit yet, from the cheapest to the most expensive city.
+#[post(
+    "/tsp/route/insertion-costs",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn route_insertion_costs(
+    input_parameters: json::Json<InsertionCostsData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let InsertionCostsData { distances, route } = input_parameters.into_inner();
+    tsp_solver::validate_distance_matrix(&distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let mut costs = (0..distances.len())
+        .filter(|city| !route.contains(city))
+        .map(|city| {
+            tsp_solver::cheapest_insertion(&distances, &route, city).map(
+                |(_, best_position, added_distance)| InsertionCost {
+                    city,
+                    best_position,
+                    added_distance,
+                },
+            )
+        })
+        .collect::<Result<Vec<InsertionCost>, String>>()
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    costs.sort_by(|a, b| {
+        a.added_distance
+            .partial_cmp(&b.added_distance)
+            .unwrap_or(cmp::Ordering::Equal)
+    });
+    Ok(json::json!({ "costs": costs }))
+}
+
+/// Remove a city from a route and connect its neighbours, and return the
+/// shortened route together with the saved distance.
+#[post("/tsp/route/remove-city", format = "json", data = "<input_parameters>")]
+fn route_remove_city(
+    input_parameters: json::Json<RouteCityData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    tsp_solver::validate_distance_matrix(&input_parameters.distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let (route, saved) = tsp_solver::remove_city(
+        &input_parameters.distances,
+        &input_parameters.route,
+        input_parameters.city,
+    )
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "distance": tsp_solver::route_distance(&input_parameters.distances, &route),
+        "route": route,
+        "delta": saved,
+    }))
+}
+
+/// Input to the `/tsp/route/delta`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct RouteDeltaData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+    edit: tsp_solver::TourEdit,
+}
+
+/// Compute how much a proposed edit changes the distance of a route from
+/// the edges it replaces, and return the edited route with the delta.
+#[post("/tsp/route/delta", format = "json", data = "<input_parameters>")]
+fn route_delta(
+    input_parameters: json::Json<RouteDeltaData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let delta = tsp_solver::edit_delta(
+        &input_parameters.distances,
+        &input_parameters.route,
+        input_parameters.edit,
+    )
+    .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "route": tsp_solver::apply_edit(&input_parameters.route, input_parameters.edit),
+        "delta": delta,
+    }))
+}
+
+/// Largest number of cities of an instance of `/tsp/generate-suite`, as
+/// the optimum is found by brute force.
+const GENERATE_SUITE_MAX_N: usize = 9;
+/// Most instances `/tsp/generate-suite` creates per request.
+const GENERATE_SUITE_MAX_COUNT: usize = 100;
+
+/// Input to the `/tsp/generate-suite`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct GenerateSuiteData {
+    count: usize,
+    n: usize,
+    seed: u64,
+}
+
+/// An instance generated by `/tsp/generate-suite` with its optimum.
+#[derive(Serialize, Deserialize)]
+struct SuiteInstance {
+    points: Vec<[f64; 3]>,
+    distances: Vec<Vec<f64>>,
+    optimal_route: Vec<usize>,
+    optimal_distance: f64,
+}
+
+/// Generate `count` random euclidean instances with `n` cities in the unit
+/// square together with their optima, as deterministic fixtures for
+/// regression tests. The same seed always yields the same suite.
+#[post("/tsp/generate-suite", format = "json", data = "<input_parameters>")]
+fn generate_suite(
+    input_parameters: json::Json<GenerateSuiteData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let GenerateSuiteData { count, n, seed } = input_parameters.into_inner();
+    if !(1..=GENERATE_SUITE_MAX_COUNT).contains(&count) || !(2..=GENERATE_SUITE_MAX_N).contains(&n)
+    {
+        return Err(status::BadRequest(json::json!(format!(
+            "count has to be in [1, {}] and n in [2, {}].",
+            GENERATE_SUITE_MAX_COUNT, GENERATE_SUITE_MAX_N
+        ))));
+    }
+    let mut rng = tsp_solver::SolverRng::seed_from_u64(seed);
+    let instances = (0..count)
+        .map(|_| {
+            let points = (0..n)
+                .map(|_| [rng.gen::<f64>(), rng.gen::<f64>(), 0.0])
+                .collect::<Vec<[f64; 3]>>();
+            let distances = tsp_solver::euclidean_matrix_3d(&points);
+            let optimal_route = tsp_solver::brute_force_route(&distances);
+            SuiteInstance {
+                optimal_distance: tsp_solver::route_distance(&distances, &optimal_route),
+                points,
+                distances,
+                optimal_route,
+            }
+        })
+        .collect::<Vec<SuiteInstance>>();
+    Ok(json::json!({ "instances": instances, "seed": seed }))
+}
+
+/// Largest number of cities of a synthetic solve in `/tsp/stress`.
+const STRESS_MAX_N: usize = 100;
+/// Largest number of parallel workers in `/tsp/stress`.
+const STRESS_MAX_CONCURRENCY: usize = 8;
+/// Longest duration in seconds of a `/tsp/stress` run.
+const STRESS_MAX_DURATION_S: f64 = 30.0;
+
+/// Input to the `/tsp/stress`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct StressData {
+    n: usize,
+    concurrency: usize,
+    duration_s: f64,
+}
+
+/// Latency percentiles of the solves of a `/tsp/stress` run.
+#[derive(Serialize, Deserialize)]
+struct LatencyPercentiles {
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+}
+
+/// Return type of the `/tsp/stress`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct StressResult {
+    completed: usize,
+    elapsed_s: f64,
+    throughput_per_s: f64,
+    latency: Option<LatencyPercentiles>,
+}
+
+/// Return the `percentile` of the ascending `sorted` values with the
+/// nearest-rank method.
+fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Run synthetic solves of random instances with `n` cities on
+/// `concurrency` threads for `duration_s` seconds to measure the
+/// throughput and latency this server can sustain. Solves that are running
+/// when the duration ends are completed and counted.
+#[post("/tsp/stress", format = "json", data = "<input_parameters>")]
+fn stress(input_parameters: json::Json<StressData>) -> Result<json::Value, SolveError> {
+    let StressData {
+        n,
+        concurrency,
+        duration_s,
+    } = input_parameters.into_inner();
+    let within_bounds = (2..=STRESS_MAX_N).contains(&n)
+        && (1..=STRESS_MAX_CONCURRENCY).contains(&concurrency)
+        && duration_s > 0.0
+        && duration_s <= STRESS_MAX_DURATION_S;
+    if !within_bounds {
+        return Err(status::BadRequest(json::json!(format!(
+            "n has to be in [2, {}], concurrency in [1, {}] and duration_s in (0, {}].",
+            STRESS_MAX_N, STRESS_MAX_CONCURRENCY, STRESS_MAX_DURATION_S
+        )))
+        .into());
+    }
+    let start = time::Instant::now();
+    let deadline = start + time::Duration::from_secs_f64(duration_s);
+    let mut latencies = thread::scope(|scope| {
+        let workers = (0..concurrency)
+            .map(|worker| {
+                scope.spawn(move || {
+                    let mut rng = tsp_solver::SolverRng::seed_from_u64(worker as u64);
+                    let mut latencies = Vec::new();
+                    while time::Instant::now() < deadline {
+                        let points = (0..n)
+                            .map(|_| [rng.gen::<f64>(), rng.gen::<f64>(), 0.0])
+                            .collect::<Vec<[f64; 3]>>();
+                        let distances = tsp_solver::euclidean_matrix_3d(&points);
+                        let solver_config = tsp_solver::SolverConfig {
+                            n_generations: 100,
+                            seed: rng.gen(),
+                            ..tsp_solver::SolverConfig::default()
+                        };
+                        let before = time::Instant::now();
+                        guard_solve(|| tsp_solver::solve_tsp_core(&distances, &solver_config))?;
+                        latencies.push(before.elapsed().as_secs_f64() * 1000.0);
+                    }
+                    Ok(latencies)
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect::<Result<Vec<Vec<f64>>, SolveError>>()
+    })?
+    .concat();
+    let elapsed_s = start.elapsed().as_secs_f64();
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+    Ok(json::json!(StressResult {
+        completed: latencies.len(),
+        elapsed_s,
+        throughput_per_s: latencies.len() as f64 / elapsed_s,
+        latency: (!latencies.is_empty()).then(|| LatencyPercentiles {
+            p50_ms: percentile(&latencies, 50.0),
+            p90_ms: percentile(&latencies, 90.0),
+            p99_ms: percentile(&latencies, 99.0),
+        }),
+    }))
+}
+
+/// Input to the `/tsp/matrix/normalize-to`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct NormalizeToData {
+    distances: Vec<Vec<f64>>,
+    min: f64,
+    max: f64,
+}
+
+/// Linearly rescale the off-diagonal distances of a matrix into
+/// `[min, max]`, keeping the zero diagonal.
+#[post(
+    "/tsp/matrix/normalize-to",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_normalize_to(
+    input_parameters: json::Json<NormalizeToData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    tsp_solver::normalize_to(
+        &input_parameters.distances,
+        input_parameters.min,
+        input_parameters.max,
+    )
+    .map(|normalized| json::json!(normalized))
+    .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Parameters of the genetic algorithm in the query string of
+/// `/tsp/solve-matrix-stream`.
+#[derive(FromForm)]
+struct StreamQuery {
+    n_generations: Option<usize>,
+    n_routes: Option<usize>,
+    top_n: Option<usize>,
+    seed: Option<u64>,
+}
+
+/// Solve the traveling-salesman-problem for a distance matrix that is
+/// uploaded as CSV with one row of the matrix per line. The body is parsed
+/// line by line while it arrives, so that malformed rows are rejected
+/// without reading the remaining upload. The parameters of the genetic
+/// algorithm are passed in the query string.
+#[post(
+    "/tsp/solve-matrix-stream?<query..>",
+    format = "text/csv",
+    data = "<upload>"
+)]
+async fn solve_matrix_stream(
+    upload: data::Data<'_>,
+    query: StreamQuery,
+    config: &State<ApiConfig>,
+    pool: &State<PopulationPool>,
+    log: RequestLog,
+) -> Result<json::Value, SolveError> {
+    // Every cell takes at most a few dozen bytes of CSV.
+    let limit = (32 * config.max_cities * config.max_cities).bytes();
+    let mut lines = rocket::tokio::io::BufReader::new(upload.open(limit)).lines();
+    let mut distances: Vec<Vec<f64>> = Vec::new();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(error) => {
+                return Err(status::BadRequest(json::json!(format!(
+                    "The upload could not be read: {}",
+                    error
+                )))
+                .into())
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row_number = distances.len() + 1;
+        let row = line
+            .split(',')
+            .map(|cell| cell.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| {
+                status::BadRequest(json::json!(format!(
+                    "Row {} contains a cell that is not a number.",
+                    row_number
+                )))
+            })?;
+        let expected_length = distances.first().map_or(row.len(), Vec::len);
+        if row.len() != expected_length {
+            return Err(status::BadRequest(json::json!(format!(
+                "Row {} has {} cells, but the first row has {}.",
+                row_number,
+                row.len(),
+                expected_length
+            )))
+            .into());
+        }
+        if row_number > config.max_cities || row.len() > config.max_cities {
+            return Err(status::BadRequest(json::json!(format!(
+                "At most {} cities are supported.",
+                config.max_cities
+            )))
+            .into());
+        }
+        distances.push(row);
+    }
+    solve_distances(
+        &distances,
+        &SolverParameters {
+            n_generations: query.n_generations.or(config.default_generations.matrix),
+            n_routes: query.n_routes,
+            top_n: query.top_n,
+            seed: query.seed,
+            ..SolverParameters::default()
+        },
+        &ResponseOptions::default(),
+        &SolveInput {
+            units: Units::Arbitrary,
+            labels: None,
+            coordinates: None,
+        },
+        config,
+        pool,
+        &log,
+    )
+    .map(|(response, _)| response)
+}
+
+/// Input to the `/tsp/candidate`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct CandidateData {
+    distances: Vec<Vec<f64>>,
+    candidates: Vec<(usize, Vec<usize>)>,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+}
+
+/// Solve with the unrestricted genetic algorithm, then refine the routes
+/// with 2-opt moves that only add legs between candidates, e.g. the
+/// neighbors of a Delaunay triangulation. Only the refinement is
+/// restricted, so the routes can keep legs between non-candidates that the
+/// genetic algorithm found. The candidates are given as
+/// `[city, [candidate, ...]]` and are used in both directions.
+#[post("/tsp/candidate", format = "json", data = "<input_parameters>")]
+fn solve_with_candidate_refinement(
+    input_parameters: json::Json<CandidateData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, SolveError> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let candidates = tsp_solver::candidate_lists(distances.len(), &input_parameters.candidates)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let solver_config = input_parameters
+        .parameters
+        .to_config()
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let mut routes = guard_solve(|| tsp_solver::solve_tsp_core(distances, &solver_config))?.routes;
+    for route in routes.iter_mut() {
+        route.indexes = tsp_solver::candidate_two_opt(
+            distances,
+            &route.indexes,
+            &candidates,
+            input_parameters.parameters.two_opt_max_passes,
+        )
+        .0;
+    }
+    tsp_solver::sort_routes(
+        &mut routes,
+        distances,
+        solver_config.objective,
+        solver_config.tie_break,
+    );
+    Ok(json::json!({
+        "routes": routes
+            .iter()
+            .map(|route| RouteWithFitness {
+                route: route.indexes.clone(),
+                fitness: tsp_solver::route_distance(distances, &route.indexes),
+                bottleneck: (solver_config.objective == tsp_solver::Objective::Bottleneck)
+                    .then(|| tsp_solver::bottleneck_distance(distances, &route.indexes)),
+                edge_contributions: None,
+                ranked_legs: None,
+                representations: RouteRepresentations::default(),
+            })
+            .collect::<Vec<RouteWithFitness>>(),
+        "seed": solver_config.seed,
+    }))
+}
+
+/// A stage of the `/tsp/solve-and-refine`-pipeline.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum PipelineStage {
+    /// The shortest nearest-neighbor route over all start cities.
+    #[serde(rename = "nn_seed")]
+    NnSeed,
+    /// The genetic algorithm, starting from the route of the previous
+    /// stage and random routes.
+    #[serde(rename = "genetic")]
+    Genetic,
+    /// 2-opt moves until no move improves the route.
+    #[serde(rename = "2-opt")]
+    TwoOpt,
+    /// Or-opt moves until no move improves the route.
+    #[serde(rename = "or-opt")]
+    OrOpt,
+}
+
+/// Input to the `/tsp/solve-and-refine`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct SolveAndRefineData {
+    distances: Vec<Vec<f64>>,
+    pipeline: Vec<PipelineStage>,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+}
+
+/// The distance of the route after a stage of the pipeline.
+#[derive(Serialize, Deserialize)]
+struct StageResult {
+    stage: PipelineStage,
+    distance: f64,
+}
+
+/// Return type of the `/tsp/solve-and-refine`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct SolveAndRefineResult {
+    route: Vec<usize>,
+    distance: f64,
+    meta: PipelineMeta,
+}
+
+/// Information about how the pipeline of `/tsp/solve-and-refine` ran.
+#[derive(Serialize, Deserialize)]
+struct PipelineMeta {
+    stages: Vec<StageResult>,
+}
+
+/// Run a pipeline of construction and improvement stages, each one
+/// starting from the route of the previous one. The pipeline has to start
+/// with a stage that builds a route, `nn_seed` or `genetic`, and `nn_seed`
+/// can only be the first stage. The parameters of the genetic algorithm
+/// are only needed if the pipeline contains a `genetic` stage.
+#[post("/tsp/solve-and-refine", format = "json", data = "<input_parameters>")]
+fn solve_and_refine(
+    input_parameters: json::Json<SolveAndRefineData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, SolveError> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let pipeline = &input_parameters.pipeline;
+    match pipeline.first() {
+        Some(PipelineStage::NnSeed | PipelineStage::Genetic) => {}
+        _ => {
+            return Err(status::BadRequest(json::json!(
+                "The pipeline has to start with nn_seed or genetic."
+            ))
+            .into())
+        }
+    }
+    if pipeline[1..].contains(&PipelineStage::NnSeed) {
+        return Err(status::BadRequest(json::json!(
+            "nn_seed can only be the first stage of the pipeline."
+        ))
+        .into());
+    }
+    // Only the genetic stage needs a configuration, which is validated
+    // before any stage runs.
+    let solver_config = pipeline
+        .contains(&PipelineStage::Genetic)
+        .then(|| input_parameters.parameters.to_config())
+        .transpose()
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let mut route: Vec<usize> = Vec::new();
+    let mut stages = Vec::with_capacity(pipeline.len());
+    for &stage in pipeline {
+        route = match stage {
+            PipelineStage::NnSeed => (0..distances.len())
+                .map(|start| tsp_solver::nearest_neighbor_route(distances, start))
+                .min_by(|a, b| {
+                    tsp_solver::route_distance(distances, a)
+                        .partial_cmp(&tsp_solver::route_distance(distances, b))
+                        .unwrap_or(cmp::Ordering::Equal)
+                })
+                .unwrap(),
+            PipelineStage::Genetic => {
+                let solver_config = solver_config.as_ref().ok_or_else(|| {
+                    status::BadRequest(json::json!("The genetic stage has no configuration."))
+                })?;
+                let mut rng = tsp_solver::SolverRng::seed_from_u64(solver_config.seed);
+                let mut population =
+                    tsp_solver::random_routes(solver_config.n_routes, distances.len(), &mut rng);
+                // The genetic algorithm keeps the fittest routes, so it never
+                // returns a route that is worse than the one it started with.
+                if !route.is_empty() {
+                    population.truncate(solver_config.n_routes.saturating_sub(1));
+                    population.insert(0, route::Route::new(route));
+                }
+                let outcome = guard_solve(|| {
+                    tsp_solver::solve_tsp_from_population(distances, solver_config, population)
+                })?;
+                outcome
+                    .routes
+                    .first()
+                    .ok_or_else(|| status::BadRequest(json::json!("No route was found.")))?
+                    .indexes
+                    .clone()
+            }
+            PipelineStage::TwoOpt => tsp_solver::two_opt(distances, &route, None).0,
+            PipelineStage::OrOpt => tsp_solver::or_opt(distances, &route).0,
+        };
+        stages.push(StageResult {
+            stage,
+            distance: tsp_solver::route_distance(distances, &route),
+        });
+    }
+    Ok(json::json!(SolveAndRefineResult {
+        distance: tsp_solver::route_distance(distances, &route),
+        route,
+        meta: PipelineMeta { stages },
+    }))
+}
+
+/// Input to the `/tsp/matrix/neighborhood-size`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct NeighborhoodSizeData {
+    n: usize,
+    k: usize,
+}
+
+/// Count the k-opt neighbors of a closed tour through `n` cities, and
+/// estimate the cost of a pass over them as the distance lookups it takes:
+/// every move removes `k` and adds `k` edges.
+#[post(
+    "/tsp/matrix/neighborhood-size",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_neighborhood_size(
+    input_parameters: json::Json<NeighborhoodSizeData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let NeighborhoodSizeData { n, k } = input_parameters.into_inner();
+    let neighbors = tsp_solver::k_opt_neighbors(n, k)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "neighbors": neighbors,
+        "reconnections_per_removal": tsp_solver::pure_reconnections(k),
+        "lookups_per_pass": neighbors as f64 * 2.0 * k as f64,
+    }))
+}
+
+/// Input to the `/tsp/open-path`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct OpenPathData {
+    distances: Vec<Vec<f64>>,
+    start: usize,
+    end: usize,
+    #[serde(flatten)]
+    parameters: SolverParameters,
+}
+
+/// Solve the shortest open path from `start` to `end` through all cities
+/// by solving the closed tour through the matrix with a dummy city that
+/// connects `end` back to `start` at no cost.
+#[post("/tsp/open-path", format = "json", data = "<input_parameters>")]
+fn solve_open_path(
+    input_parameters: json::Json<OpenPathData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, SolveError> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let augmented =
+        tsp_solver::open_path_matrix(distances, input_parameters.start, input_parameters.end)
+            .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let solver_config = input_parameters
+        .parameters
+        .clone()
+        .or_generations(config.default_generations.matrix)
+        .to_config()
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let outcome = guard_solve(|| tsp_solver::solve_tsp_core(&augmented, &solver_config))?;
+    let tour = outcome
+        .routes
+        .first()
+        .ok_or_else(|| status::BadRequest(json::json!("No route was found.")))?;
+    let route = tsp_solver::open_path_from_tour(
+        &tour.indexes,
+        input_parameters.start,
+        input_parameters.end,
+    );
+    let distance = tsp_solver::segment_distance(distances, &route, 0, route.len() - 1)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!({
+        "route": route,
+        "distance": distance,
+        "seed": solver_config.seed,
+    }))
+}
+
+/// Input to the `/tsp/polish`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct PolishData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+}
+
+/// Return type of the `/tsp/polish`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct PolishResult {
+    route: Vec<usize>,
+    distance: f64,
+    n_moves: usize,
+    improvement: f64,
+}
+
+/// Polish a route to a local optimum by applying 2-opt and Or-opt moves
+/// until neither shortens it any further.
+#[post("/tsp/polish", format = "json", data = "<input_parameters>")]
+fn polish(
+    input_parameters: json::Json<PolishData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    tsp_solver::validate_distance_matrix(distances)
+        .and_then(|_| tsp_solver::validate_route(&input_parameters.route, distances.len()))
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let (route, n_moves, improvement) = tsp_solver::polish(distances, &input_parameters.route);
+    Ok(json::json!(PolishResult {
+        distance: tsp_solver::route_distance(distances, &route),
+        route,
+        n_moves,
+        improvement,
+    }))
+}
+
+/// Input to the `/tsp/matrix/zero-diagonal`-endpoint. The diagonal may be
+/// `null`, all other entries have to be numbers.
+#[derive(Serialize, Deserialize)]
+struct ZeroDiagonalData {
+    distances: Vec<Vec<Option<f64>>>,
+}
+
+/// Return the matrix with its diagonal set to zero and all other entries
+/// unchanged.
+#[post(
+    "/tsp/matrix/zero-diagonal",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_zero_diagonal(
+    input_parameters: json::Json<ZeroDiagonalData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = input_parameters
+        .distances
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(to, distance)| match distance {
+                    Some(distance) => Ok(*distance),
+                    None if from == to => Ok(0.0),
+                    None => Err(format!("The distance from {} to {} is missing.", from, to)),
+                })
+                .collect::<Result<Vec<f64>, String>>()
+        })
+        .collect::<Result<Vec<Vec<f64>>, String>>()
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let zeroed = tsp_solver::zero_diagonal(&distances);
+    tsp_solver::validate_distance_matrix(&zeroed)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!(zeroed))
+}
+
+/// Return the strict upper triangle of a matrix as jagged rows for compact
+/// storage of symmetric matrices. If the matrix is not symmetric the lower
+/// triangle is lost, which is reported in `warnings`.
+#[post(
+    "/tsp/matrix/upper-triangle",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_upper_triangle(
+    input_parameters: json::Json<MatrixData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    tsp_solver::validate_distance_matrix(distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let mut warnings = Vec::new();
+    if !tsp_solver::is_symmetric(distances, 1e-9) {
+        warnings.push(String::from(
+            "The matrix is not symmetric, the distances below the diagonal are dropped.",
+        ));
+    }
+    Ok(json::json!({
+        "triangle": tsp_solver::upper_triangle(distances),
+        "warnings": warnings,
+    }))
+}
+
+/// Input to the `/tsp/matrix/quantize`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct QuantizeData {
+    distances: Vec<Vec<f64>>,
+    bucket_size: f64,
+}
+
+/// Round every distance of a matrix to the nearest multiple of
+/// `bucket_size`, keeping the zero diagonal.
+#[post("/tsp/matrix/quantize", format = "json", data = "<input_parameters>")]
+fn matrix_quantize(
+    input_parameters: json::Json<QuantizeData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    tsp_solver::quantize(&input_parameters.distances, input_parameters.bucket_size)
+        .map(|quantized| json::json!(quantized))
+        .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Input to the `/tsp/matrix/compress`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct CompressData {
+    distances: Vec<Vec<f64>>,
+    scale: Option<f64>,
+}
+
+/// A distance matrix stored as integer multiples of `scale`, returned by
+/// `/tsp/matrix/compress` and taken by `/tsp/matrix/decompress`.
+#[derive(Serialize, Deserialize)]
+struct CompressedMatrix {
+    scale: f64,
+    ints: Vec<Vec<u32>>,
+}
+
+/// Quantize a matrix to integers with a scale factor for compact storage.
+/// Every distance is recovered by `/tsp/matrix/decompress` up to half the
+/// scale.
+#[post("/tsp/matrix/compress", format = "json", data = "<input_parameters>")]
+fn matrix_compress(
+    input_parameters: json::Json<CompressData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let (scale, ints) = tsp_solver::compress(&input_parameters.distances, input_parameters.scale)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!(CompressedMatrix { scale, ints }))
+}
+
+/// Recover a distance matrix that was compressed by `/tsp/matrix/compress`.
+#[post("/tsp/matrix/decompress", format = "json", data = "<input_parameters>")]
+fn matrix_decompress(
+    input_parameters: json::Json<CompressedMatrix>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    if !(input_parameters.scale.is_finite() && input_parameters.scale > 0.0) {
+        return Err(status::BadRequest(json::json!("scale has to be positive.")));
+    }
+    let distances = tsp_solver::decompress(input_parameters.scale, &input_parameters.ints);
+    tsp_solver::validate_distance_matrix(&distances)
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    Ok(json::json!(distances))
+}
+
+/// Serialize a matrix back to JSON and parse it again, and report whether
+/// the values survive the round-trip and the largest difference if not.
+#[post("/tsp/matrix/round-trip", format = "json", data = "<input_parameters>")]
+fn matrix_round_trip(
+    input_parameters: json::Json<MatrixData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    let reparsed: Vec<Vec<f64>> = json::to_string(distances)
+        .ok()
+        .and_then(|serialized| json::from_str(&serialized).ok())
+        .ok_or_else(|| {
+            status::BadRequest(json::json!(
+                "The matrix could not be serialized to JSON again."
+            ))
+        })?;
+    let max_diff = distances
+        .iter()
+        .flatten()
+        .zip(reparsed.iter().flatten())
+        .map(|(original, parsed)| (original - parsed).abs())
+        .fold(0.0, f64::max);
+    Ok(json::json!({
+        "stable": reparsed == *distances,
+        "max_diff": max_diff,
+    }))
+}
+
+/// Input to the `/tsp/evaluate/batch`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct EvaluateBatchData {
+    distances: Vec<Vec<f64>>,
+    routes: Vec<Vec<usize>>,
+}
+
+/// Return type of the `/tsp/evaluate/batch`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct EvaluateBatchResult {
+    distances: Vec<f64>,
+    cache_hits: usize,
+}
+
+/// Compute the distance of many closed tours at once. Routes that occur
+/// more than once in a request are only scored once; `cache_hits` counts
+/// how often a distance was reused.
+#[post("/tsp/evaluate/batch", format = "json", data = "<input_parameters>")]
+fn evaluate_batch(
+    input_parameters: json::Json<EvaluateBatchData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    let mut memo: HashMap<&[usize], f64> = HashMap::new();
+    let mut cache_hits = 0;
+    let mut route_distances = Vec::with_capacity(input_parameters.routes.len());
+    for (index, route) in input_parameters.routes.iter().enumerate() {
+        let distance = match memo.get(route.as_slice()) {
+            Some(&distance) => {
+                cache_hits += 1;
+                distance
+            }
+            None => {
+                tsp_solver::validate_route(route, distances.len()).map_err(|message| {
+                    status::BadRequest(json::json!(format!("Route {}: {}", index, message)))
+                })?;
+                let distance = tsp_solver::route_distance(distances, route);
+                memo.insert(route, distance);
+                distance
+            }
+        };
+        route_distances.push(distance);
+    }
+    Ok(json::json!(EvaluateBatchResult {
+        distances: route_distances,
+        cache_hits,
+    }))
+}
+
+/// Largest gap to the optimum in percent that is still graded as good.
+const GOOD_GRADE_MAX_GAP_PERCENT: f64 = 10.0;
+
+/// How close a route is to the optimal one.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Grade {
+    /// As short as the optimal route.
+    Optimal,
+    /// At most `GOOD_GRADE_MAX_GAP_PERCENT` longer than the optimal route.
+    Good,
+    /// Longer than that.
+    Poor,
+}
+
+/// Input to the `/tsp/route/grade`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct GradeData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+}
+
+/// Return type of the `/tsp/route/grade`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct GradeResult {
+    your_distance: f64,
+    optimal_distance: f64,
+    gap_percent: f64,
+    grade: Grade,
+}
+
+/// Compare a route to the optimal one, which is found by brute force. Only
+/// instances of at most `max_exact_n` cities are graded.
+#[post("/tsp/route/grade", format = "json", data = "<input_parameters>")]
+fn grade_route(
+    input_parameters: json::Json<GradeData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    let distances = &input_parameters.distances;
+    validate_distances(distances, config)?;
+    if distances.len() > config.max_exact_n {
+        return Err(status::BadRequest(json::json!(format!(
+            "Only routes through at most {} cities can be graded, but {} were given.",
+            config.max_exact_n,
+            distances.len()
+        ))));
+    }
+    tsp_solver::validate_route(&input_parameters.route, distances.len())
+        .map_err(|message| status::BadRequest(json::json!(message)))?;
+    let your_distance = tsp_solver::route_distance(distances, &input_parameters.route);
+    let optimal_distance =
+        tsp_solver::route_distance(distances, &tsp_solver::brute_force_route(distances));
+    let gap_percent = if optimal_distance > 0.0 {
+        100.0 * (your_distance - optimal_distance) / optimal_distance
+    } else {
+        0.0
+    };
+    // Allow for rounding when the same tour is summed in another order.
+    let grade = if your_distance - optimal_distance <= 1e-9 * optimal_distance.max(1.0) {
+        Grade::Optimal
+    } else if gap_percent <= GOOD_GRADE_MAX_GAP_PERCENT {
+        Grade::Good
+    } else {
+        Grade::Poor
+    };
+    Ok(json::json!(GradeResult {
+        your_distance,
+        optimal_distance,
+        gap_percent,
+        grade,
+    }))
+}
+
+/// Input to the `/tsp/route/segment`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct SegmentData {
+    distances: Vec<Vec<f64>>,
+    route: Vec<usize>,
+    start_index: usize,
+    end_index: usize,
+}
+
+/// Return the distance of the path between two positions of a route, which
+/// does not wrap around to the start.
+#[post("/tsp/route/segment", format = "json", data = "<input_parameters>")]
+fn route_segment(
+    input_parameters: json::Json<SegmentData>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    tsp_solver::segment_distance(
+        &input_parameters.distances,
+        &input_parameters.route,
+        input_parameters.start_index,
+        input_parameters.end_index,
+    )
+    .map(|distance| json::json!({ "distance": distance }))
+    .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// Input to the `/tsp/matrix/from-edges-weighted-undirected`-endpoint.
+#[derive(Serialize, Deserialize)]
+struct UndirectedEdgesData {
+    n: usize,
+    edges: Vec<(usize, usize, f64)>,
+    sentinel: f64,
+}
+
+/// Build a symmetric distance matrix from undirected, weighted edges
+/// `[a, b, w]`. Pairs without an edge are set to `sentinel`, so that the
+/// result can be passed on to `/tsp/matrix/complete`.
+#[post(
+    "/tsp/matrix/from-edges-weighted-undirected",
+    format = "json",
+    data = "<input_parameters>"
+)]
+fn matrix_from_undirected_edges(
+    input_parameters: json::Json<UndirectedEdgesData>,
+    config: &State<ApiConfig>,
+) -> Result<json::Value, status::BadRequest<json::Value>> {
+    if input_parameters.n > config.max_cities {
+        return Err(status::BadRequest(json::json!(format!(
+            "At most {} cities are supported, but {} were given.",
+            config.max_cities, input_parameters.n
+        ))));
+    }
+    tsp_solver::undirected_edges_to_matrix(
+        input_parameters.n,
+        &input_parameters.edges,
+        input_parameters.sentinel,
+    )
+    .map(|distances| json::json!(distances))
+    .map_err(|message| status::BadRequest(json::json!(message)))
+}
+
+/// If an enpoint cannot be found, return "Not found!"
+#[catch(404)]
+fn not_found() -> json::Value {
+    json::json!("Not found!")
+}
+
+/// Message of the responses of failed computations.
+const FAILED_COMPUTATION_MESSAGE: &str = "Your computation could not be done.";
+
+/// If an internal server error happens, return "Your computation could not be done."
+#[catch(500)]
+fn failed_computation() -> json::Value {
+    json::json!(FAILED_COMPUTATION_MESSAGE)
+}
+
+/// Build Rocket API.
+#[launch]
+fn rocket() -> _ {
+    build_rocket(ApiConfig::from_env())
+}
+
+/// Build the Rocket API with an explicit configuration.
+fn build_rocket(config: ApiConfig) -> Rocket<Build> {
+    rocket::build()
+        .manage(config)
+        .manage(ProfileStore::default())
+        .manage(PopulationPool::default())
+        .mount(
+            "/",
+            routes![
+                liveness_probe,
+                capabilities,
+                solve_tsp,
+                solve_coordinates_3d,
+                register_profile,
+                matrix_submatrix,
+                matrix_complete,
+                route_two_opt_step,
+                matrix_metric_closure,
+                bracket,
+                matrix_hash,
+                matrix_from_durations,
+                route_repair,
+                matrix_from_adjacency,
+                solve_geo,
+                solve_response_schema,
+                matrix_cluster,
+                cluster_solve,
+                route_insert_city,
+                route_remove_city,
+                stress,
+                matrix_normalize_to,
+                solve_matrix_stream,
+                polish,
+                matrix_quantize,
+                matrix_round_trip,
+                evaluate_batch,
+                route_segment,
+                matrix_from_undirected_edges,
+                random_baseline,
+                solve_reverse,
+                grade_route,
+                warm_pool,
+                matrix_zero_diagonal,
+                solve_with_candidate_refinement,
+                solve_and_refine,
+                matrix_upper_triangle,
+                solve_open_path,
+                route_delta,
+                generate_suite,
+                metrics,
+                matrix_neighborhood_size,
+                route_hash,
+                matrix_compress,
+                matrix_decompress,
+                route_insertion_costs,
+            ],
+        )
+        .register("/", catchers![not_found, failed_computation])
+}
+
+#[cfg(test)]
+mod test {
+    use super::rocket;
+    use super::*;
+    use rocket::http;
+    use rocket::local::blocking;
+
+    #[test]
+    fn test_not_found() {
+        // Test that for an unkown route, "Not found" is returned
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client.get("/does/not/exist").dispatch();
+        assert_eq!(response.status(), http::Status { code: 404 });
+        assert_eq!(response.content_type(), Some(http::ContentType::JSON));
+        assert_eq!(
+            response.into_string().unwrap(),
+            String::from(r##""Not found!""##)
+        );
+    }
+    #[test]
+    fn test_liveness() {
+        // Test the liveness probe.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client.get("/alive").dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        assert_eq!(response.content_type(), Some(http::ContentType::JSON));
+        assert_eq!(
+            response.into_string().unwrap(),
+            String::from(r##""alive""##)
+        );
+    }
+    #[test]
+    fn test_tsp() {
+        // Check that the enpoint works with a realistic request.
+        // I make the following assumptions on the reponse:
+        // - 200 status code
+        // - Return type is json
+        // - Return value can be deserialized into SolveTspResponse
+        // - There are three solutions returned.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations":  10000
+                }"##,
+            )
+            .dispatch();
+
+        assert_eq!(response.status(), http::Status::Ok);
+        assert_eq!(response.content_type(), Some(http::ContentType::JSON));
+        let returned_routes =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap())
+                .unwrap()
+                .routes;
+        assert_eq!(returned_routes.len(), 3);
+    }
+    #[test]
+    fn test_matrix_submatrix() {
+        // The returned sub-matrix contains the entries of the original
+        // matrix for the requested cities.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/submatrix")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "indices": [0, 2, 5]
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let sub: Vec<Vec<f64>> = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            sub,
+            vec![
+                vec![0.0, 378.0, 200.0],
+                vec![378.0, 0.0, 344.0],
+                vec![200.0, 344.0, 0.0]
+            ]
+        );
+    }
+    #[test]
+    fn test_matrix_submatrix_invalid_indices() {
+        // Duplicated indices are rejected with a 400.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/submatrix")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1],[1,0]], "indices": [1, 1]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        // So are matrices with a short row.
+        let response = client
+            .post("/tsp/matrix/submatrix")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1],[1]], "indices": [1, 0]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_with_profile() {
+        // Register a profile and solve with it. Parameters that are not
+        // given in the request are taken from the profile.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/profiles")
+            .header(http::ContentType::JSON)
+            .body(r##"{"name": "nightly", "n_generations": 100, "n_routes": 12, "top_n": 2}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+
+        let request_body = r##"{
+            "distances": [
+                [0,64,378,519,434,200],
+                [64,0,318,455,375,164],
+                [378,318,0,170,265,344],
+                [519,455,170,0,223,428],
+                [434,375,265,223,0,273],
+                [200,164,344,428,273,0]],
+            "profile": "nightly",
+            "top_n": 1,
+            "echo_input": true
+            }"##;
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(request_body)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).unwrap();
+        // `top_n` of the request overrides the one of the profile.
+        assert_eq!(solution.routes.len(), 1);
+
+        // The profile's `n_routes` was applied by the solve.
+        let parameters = solution.meta.input.unwrap().parameters;
+        assert_eq!(parameters.n_routes, Some(12));
+        assert_eq!(parameters.n_generations, Some(100));
+        assert_eq!(parameters.top_n, Some(1));
+    }
+    #[test]
+    fn test_tsp_unknown_profile() {
+        // Referencing a profile that was never registered is a bad request.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1],[1,0]], "profile": "does-not-exist"}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_explain_edges() {
+        // With `explain_edges` the best route reports one contribution per
+        // leg of the closed tour and the percentages sum up to 100.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 100,
+                "explain_edges": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let returned_routes =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap())
+                .unwrap()
+                .routes;
+        let contributions = returned_routes[0].edge_contributions.as_ref().unwrap();
+        assert_eq!(contributions.len(), returned_routes[0].route.len());
+        let total_percent: f64 = contributions
+            .iter()
+            .map(|contribution| contribution.percent_of_total)
+            .sum();
+        assert!((total_percent - 100.0).abs() < 1e-6);
+        assert!(returned_routes[1].edge_contributions.is_none());
+    }
+    #[test]
+    fn test_coordinates_3d() {
+        // Solving a few points in 3D space returns valid permutations of
+        // all points.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/coordinates/3d")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "points": [[0,0,0],[1,0,0],[1,1,0],[0,1,0],[0,0,2]],
+                "n_generations": 100
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let returned_routes =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap())
+                .unwrap()
+                .routes;
+        assert_eq!(returned_routes.len(), 3);
+        for returned_route in returned_routes {
+            let mut route = returned_route.route.clone();
+            route.sort_unstable();
+            assert_eq!(route, vec![0, 1, 2, 3, 4]);
+        }
+    }
+    #[test]
+    fn test_capabilities() {
+        // The capabilities reflect the configuration the server runs with.
+        let client = blocking::Client::tracked(build_rocket(ApiConfig {
+            max_cities: 42,
+            max_exact_n: 7,
+            max_tour_distance: DEFAULT_MAX_TOUR_DISTANCE,
+            max_meta_points: None,
+            default_generations: DefaultGenerations::default(),
+        }))
+        .unwrap();
+        let response = client.get("/capabilities").dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let capabilities: json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(capabilities["max_cities"], 42);
+        assert_eq!(capabilities["max_exact_n"], 7);
+        assert!(capabilities["endpoints"]
+            .as_array()
+            .unwrap()
+            .contains(&json::json!("POST /tsp")));
+    }
+    #[test]
+    fn test_tsp_too_many_cities() {
+        // Matrices larger than the configured limit are rejected.
+        let client = blocking::Client::tracked(build_rocket(ApiConfig {
+            max_cities: 2,
+            max_exact_n: 2,
+            max_tour_distance: DEFAULT_MAX_TOUR_DISTANCE,
+            max_meta_points: None,
+            default_generations: DefaultGenerations::default(),
+        }))
+        .unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "n_generations": 10}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_matrix_complete() {
+        // After completing the matrix no sentinel is left.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/complete")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [[0,5,-1],[5,0,2],[-1,2,0]],
+                "sentinel": -1,
+                "strategy": "shortest_path"
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let completed: Vec<Vec<f64>> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(completed.iter().flatten().all(|&distance| distance != -1.0));
+        assert_eq!(completed[0][2], 7.0);
+        // Non-square matrices and matrices above the size limit are rejected.
+        let complete = |client: &blocking::Client, distances: &str| {
+            client
+                .post("/tsp/matrix/complete")
+                .header(http::ContentType::JSON)
+                .body(format!(
+                    r##"{{"distances": {}, "sentinel": -1, "strategy": "shortest_path"}}"##,
+                    distances
+                ))
+                .dispatch()
+                .status()
+        };
+        assert_eq!(complete(&client, "[[0,-1],[-1]]"), http::Status::BadRequest);
+        let small = blocking::Client::tracked(build_rocket(ApiConfig {
+            max_cities: 2,
+            ..ApiConfig::from_env()
+        }))
+        .unwrap();
+        assert_eq!(
+            complete(&small, "[[0,5,-1],[5,0,2],[-1,2,0]]"),
+            http::Status::BadRequest
+        );
+    }
+    #[test]
+    fn test_tsp_invalid_crossover_rate() {
+        // A crossover rate outside of [0, 1] is rejected.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1],[1,0]], "n_generations": 10, "crossover_rate": 1.5}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_zero_generations() {
+        // A run without generations is rejected before it is solved.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/open-path")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "start": 0, "end": 2, "n_generations": 0}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert!(response.into_string().unwrap().contains("n_generations"));
+    }
+    #[test]
+    fn test_tsp_too_many_generations() {
+        // The mutation schedule has at most `MAX_GENERATIONS` steps.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1],[1,0]], "n_generations": 10001}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert!(response.into_string().unwrap().contains("n_generations"));
+    }
+    #[test]
+    fn test_tsp_zero_routes() {
+        // An empty population is rejected.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/candidate")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "candidates": [], "n_generations": 10, "n_routes": 0}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert!(response.into_string().unwrap().contains("n_routes"));
+    }
+    #[test]
+    fn test_tsp_zero_top_n() {
+        // At least the best route has to be returned.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/solve-and-refine")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "pipeline": ["genetic"], "n_generations": 10, "top_n": 0}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert!(response.into_string().unwrap().contains("top_n"));
+    }
+    #[test]
+    fn test_route_two_opt_step() {
+        // The best improving step never increases the distance and reports
+        // which segment was reversed.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = vec![0, 3, 1, 4, 2, 5];
+        let response = client
+            .post("/tsp/route/two-opt-step")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "route": route}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let step: TwoOptStepResult =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(step.distance <= tsp_solver::route_distance(&distances, &route));
+        let [i, j] = step.reversed.unwrap();
+        assert_eq!(step.route, tsp_solver::two_opt_move(&route, i, j));
+
+        // A specified move is applied as given.
+        let response = client
+            .post("/tsp/route/two-opt-step")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "route": route, "i": 1, "j": 3}))
+            .dispatch();
+        let step: TwoOptStepResult =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(step.route, vec![0, 4, 1, 3, 2, 5]);
+        assert_eq!(step.reversed, Some([1, 3]));
+
+        // A matrix with a short row is rejected.
+        let response = client
+            .post("/tsp/route/two-opt-step")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1,2,3],[1,0,4],[2,4,0,6],[3,5,6,0]], "route": [0,1,2,3]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_reports_seed() {
+        // Without a seed in the request the generated seed is reported and
+        // reproduces the routes when it is sent back.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = json::json!([
+            [0, 64, 378, 519, 434, 200],
+            [64, 0, 318, 455, 375, 164],
+            [378, 318, 0, 170, 265, 344],
+            [519, 455, 170, 0, 223, 428],
+            [434, 375, 265, 223, 0, 273],
+            [200, 164, 344, 428, 273, 0]
+        ]);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "n_generations": 50}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let first: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": distances,
+                "n_generations": 50,
+                "seed": first.meta.seed
+            }))
+            .dispatch();
+        let second: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(second.meta.seed, first.meta.seed);
+        assert_eq!(
+            first
+                .routes
+                .iter()
+                .map(|route| route.route.clone())
+                .collect::<Vec<Vec<usize>>>(),
+            second
+                .routes
+                .iter()
+                .map(|route| route.route.clone())
+                .collect::<Vec<Vec<usize>>>()
+        );
+    }
+    #[test]
+    fn test_matrix_metric_closure() {
+        // The closure of a non-metric matrix satisfies the triangle inequality.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/metric-closure")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1,5],[1,0,1],[5,1,0]]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let closure: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let distances: Vec<Vec<f64>> =
+            serde_json::from_value(closure["distances"].clone()).unwrap();
+        assert!(tsp_solver::satisfies_triangle_inequality(&distances, 1e-9));
+        assert_eq!(closure["changed_cells"], 2);
+        // Jagged matrices and negative distances are rejected.
+        for distances in ["[[0,1],[1]]", "[[0,-1],[-1,0]]"] {
+            let response = client
+                .post("/tsp/matrix/metric-closure")
+                .header(http::ContentType::JSON)
+                .body(format!(r##"{{"distances": {}}}"##, distances))
+                .dispatch();
+            assert_eq!(response.status(), http::Status::BadRequest);
+        }
+    }
+    #[test]
+    fn test_tsp_init_strategies() {
+        // All initialization strategies can be requested.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        for init in ["random", "nearest_neighbor", "greedy_edge"] {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .json(&json::json!({
+                    "distances": [[0,1,2,3],[1,0,4,5],[2,4,0,6],[3,5,6,0]],
+                    "n_generations": 10,
+                    "n_routes": 5,
+                    "init": init
+                }))
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+        }
+    }
+    #[test]
+    fn test_tsp_canonical() {
+        // Two runs with different seeds that find the optimal tour return it
+        // in byte-identical canonical form.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let solve = |seed: u64| {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .json(&json::json!({
+                    "distances": [
+                        [0, 64, 378, 519, 434, 200],
+                        [64, 0, 318, 455, 375, 164],
+                        [378, 318, 0, 170, 265, 344],
+                        [519, 455, 170, 0, 223, 428],
+                        [434, 375, 265, 223, 0, 273],
+                        [200, 164, 344, 428, 273, 0]],
+                    "n_generations": 1000,
+                    "seed": seed,
+                    "top_n": 1,
+                    "canonical": true
+                }))
+                .dispatch();
+            let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            assert_eq!(body["routes"][0]["fitness"], 1248.0);
+            body["routes"][0]["route"].to_string()
+        };
+        assert_eq!(solve(1), solve(2));
+    }
+    #[test]
+    fn test_bracket_invalid_parameters() {
+        // Invalid generations and too many restarts are rejected.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        for body in [
+            r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "n_generations": 0}"##,
+            r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "n_generations": 20000}"##,
+            r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "n_restarts": 1000000}"##,
+        ] {
+            let response = client
+                .post("/tsp/bracket")
+                .header(http::ContentType::JSON)
+                .body(body)
+                .dispatch();
+            assert_eq!(response.status(), http::Status::BadRequest);
+        }
+    }
+    #[test]
+    fn test_bracket() {
+        // The lower bound never exceeds the best tour found.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/bracket")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_restarts": 2,
+                "n_generations": 50
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let bracket: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let best_found = bracket["best_found"].as_f64().unwrap();
+        let lower_bound = bracket["lower_bound"].as_f64().unwrap();
+        assert!(lower_bound <= best_found);
+        assert!(best_found >= 1248.0);
+        assert!(bracket["estimated_gap"].as_f64().unwrap() >= 0.0);
+    }
+    #[test]
+    fn test_matrix_hash() {
+        // The same matrix hashes identically and a changed cell changes the
+        // hash.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let hash = |body: &str| {
+            let response = client
+                .post("/tsp/matrix/hash")
+                .header(http::ContentType::JSON)
+                .body(body)
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            body["hash"].as_str().unwrap().to_string()
+        };
+        let original = hash(r##"{"distances": [[0,1],[1,0]]}"##);
+        assert_eq!(original, hash(r##"{"distances": [[0,1],[1,0]]}"##));
+        assert_ne!(original, hash(r##"{"distances": [[0,2],[1,0]]}"##));
+        assert_ne!(
+            original,
+            hash(r##"{"distances": [[0,1],[1,0]], "config": {"n_generations": 10}}"##)
+        );
+    }
+    #[test]
+    fn test_tsp_two_opt_max_passes() {
+        // The number of 2-opt passes reported never exceeds the bound.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 10,
+                "two_opt_max_passes": 1
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body.meta.two_opt_passes_run, Some(1));
+    }
+    #[test]
+    fn test_matrix_from_durations() {
+        // Durations are multiplied by the speed and the diagonal is zero.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/from-durations")
+            .header(http::ContentType::JSON)
+            .body(r##"{"durations": [[0,2,3],[2,0,1],[3,1,0]], "speed": 60}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let distances: Vec<Vec<f64>> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            distances,
+            vec![
+                vec![0.0, 120.0, 180.0],
+                vec![120.0, 0.0, 60.0],
+                vec![180.0, 60.0, 0.0]
+            ]
+        );
+        let response = client
+            .post("/tsp/matrix/from-durations")
+            .header(http::ContentType::JSON)
+            .body(r##"{"durations": [[0,2],[2,0]], "speed": -1}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_avg_history() {
+        // The average history has one entry per generation run and ends
+        // above the best distance.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 40,
+                "include": ["avg_history"]
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let avg_history = body.meta.avg_history.unwrap();
+        assert_eq!(avg_history.len(), body.meta.generations_run);
+        assert!(*avg_history.last().unwrap() >= body.routes[0].fitness);
+    }
+    #[test]
+    fn test_tsp_verify_2opt() {
+        // A route refined with 2-opt is verified to be 2-opt optimal.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 10,
+                "two_opt": true,
+                "verify_2opt": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body.meta.is_2opt_optimal, Some(true));
+    }
+    #[test]
+    fn test_tsp_include() {
+        // Only the requested statistics are returned.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [[0,1,2,3],[1,0,4,5],[2,4,0,6],[3,5,6,0]],
+                "n_generations": 20,
+                "n_routes": 5,
+                "include": ["history"]
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["meta"]["history"].as_array().unwrap().len(), 20);
+        assert!(body["meta"].get("diversity").is_none());
+        assert!(body["meta"].get("avg_history").is_none());
+
+        // Unknown fields are rejected.
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,1],[1,0]], "n_generations": 10, "include": ["foo"]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::UnprocessableEntity);
+    }
+    #[test]
+    fn test_route_repair() {
+        // A duplicate is replaced by the missing city in place.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/route/repair")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"route": [4, 0, 2, 0, 1], "n": 5}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let repaired: RepairRouteResult =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(repaired.route, vec![4, 0, 2, 3, 1]);
+        assert_eq!(
+            repaired.edits,
+            vec![tsp_solver::RouteEdit::Replace {
+                index: 3,
+                from: 0,
+                to: 3
+            }]
+        );
+    }
+    #[test]
+    fn test_tsp_sort_worst() {
+        // `worst` returns the same routes in reverse order of distance.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let request = json::json!({
+            "distances": [
+                [0, 64, 378, 519, 434, 200],
+                [64, 0, 318, 455, 375, 164],
+                [378, 318, 0, 170, 265, 344],
+                [519, 455, 170, 0, 223, 428],
+                [434, 375, 265, 223, 0, 273],
+                [200, 164, 344, 428, 273, 0]
+            ],
+            "n_generations": 20,
+            "n_routes": 20,
+            "top_n": 5,
+            "seed": 7
+        });
+        let mut worst_request = request.clone();
+        worst_request["sort"] = json::json!("worst");
+        let solve = |request: &json::Value| -> SolveTspResponse {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .json(request)
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            serde_json::from_str(&response.into_string().unwrap()).unwrap()
+        };
+        let best_first = solve(&request).routes;
+        let mut worst_first = solve(&worst_request).routes;
+        assert!(worst_first
+            .windows(2)
+            .all(|pair| pair[0].fitness >= pair[1].fitness));
+        worst_first.reverse();
+        assert_eq!(
+            worst_first
+                .iter()
+                .map(|route| &route.route)
+                .collect::<Vec<_>>(),
+            best_first
+                .iter()
+                .map(|route| &route.route)
+                .collect::<Vec<_>>()
+        );
+    }
+    #[test]
+    fn test_matrix_from_adjacency() {
+        // A triangle 0 - 1 - 2 and a disconnected city 3.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/from-adjacency")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"n": 4, "edges": [[0, 1], [1, 2]]}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            body["distances"],
+            json::json!([
+                [0.0, 1.0, 2.0, null],
+                [1.0, 0.0, 1.0, null],
+                [2.0, 1.0, 0.0, null],
+                [null, null, null, 0.0]
+            ])
+        );
+        assert_eq!(
+            body["unreachable"],
+            json::json!([[0, 3], [1, 3], [2, 3], [3, 0], [3, 1], [3, 2]])
+        );
+
+        // Edges to unknown cities are rejected.
+        let response = client
+            .post("/tsp/matrix/from-adjacency")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"n": 2, "edges": [[0, 5]]}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_solve_response_units() {
+        // Geo responses are in km, raw matrices in arbitrary units, and both
+        // point to a schema that is served by the API.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": [[52.52, 13.405], [48.8566, 2.3522], [50.1109, 8.6821]],
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let geo: SolveTspResponse = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(geo.units, Units::Km);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": [[0, 1], [1, 0]], "n_generations": 10}))
+            .dispatch();
+        let matrix: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(matrix.units, Units::Arbitrary);
+        let response = client.get(matrix.schema).dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+    }
+    #[test]
+    fn test_matrix_cluster() {
+        // Every city ends up in exactly one of the k clusters.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/cluster")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "k": 3
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let clustering: tsp_solver::Clustering =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(clustering.clusters.len(), 3);
+        let mut cities = clustering.clusters.concat();
+        cities.sort_unstable();
+        assert_eq!(cities, vec![0, 1, 2, 3, 4, 5]);
+    }
+    #[test]
+    fn test_cluster_solve() {
+        // The stitched route over four groups of points visits every city
+        // exactly once.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let points = (0..40)
+            .map(|city| {
+                let group = f64::from(city % 4);
+                [
+                    100.0 * group + f64::from(city % 7),
+                    f64::from(city % 5),
+                    0.0,
+                ]
+            })
+            .collect::<Vec<[f64; 3]>>();
+        let response = client
+            .post("/tsp/cluster-solve")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": tsp_solver::euclidean_matrix_3d(&points),
+                "k": 4,
+                "n_generations": 50
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let route: Vec<usize> = serde_json::from_value(body["route"].clone()).unwrap();
+        assert!(tsp_solver::validate_route(&route, 40).is_ok());
+    }
+    #[test]
+    fn test_cluster_solve_invalid_generations() {
+        // The generations are validated although two clusters of two
+        // cities are never solved.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        for n_generations in [0, 20000] {
+            let response = client
+                .post("/tsp/cluster-solve")
+                .header(http::ContentType::JSON)
+                .json(&json::json!({
+                    "distances": [[0,1,10,11],[1,0,9,10],[10,9,0,1],[11,10,1,0]],
+                    "k": 2,
+                    "n_generations": n_generations
+                }))
+                .dispatch();
+            assert_eq!(response.status(), http::Status::BadRequest);
+        }
+    }
+    #[test]
+    fn test_tsp_phase_timings() {
+        // The phase timings add up to the total computation time, up to the
+        // rounding of every phase to whole milliseconds.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "n_generations": 1000,
+                "two_opt": true
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let meta = serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap())
+            .unwrap()
+            .meta;
+        let timings = meta.timings;
+        let sum = timings.validate_ms + timings.build_ms + timings.solve_ms + timings.refine_ms;
+        assert!(sum <= meta.computation_ms);
+        assert!(meta.computation_ms - sum <= 3);
+    }
+    #[test]
+    fn test_route_insert_city() {
+        // The city is inserted where it adds the least distance.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/route/insert-city")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 2, 1], [1, 0, 1, 2], [2, 1, 0, 1], [1, 2, 1, 0]],
+                "route": [0, 2, 3],
+                "city": 1
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["route"], json::json!([0, 1, 2, 3]));
+        assert_eq!(body["delta"], json::json!(0.0));
+        assert_eq!(body["distance"], json::json!(4.0));
+
+        // Cities that are already visited are rejected.
+        let response = client
+            .post("/tsp/route/insert-city")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": [[0, 1], [1, 0]], "route": [0, 1], "city": 1}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+
+        // So are matrices with a short row.
+        let response = client
+            .post("/tsp/route/insert-city")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 2, 1], [1, 0, 1, 2], [2, 1], [1, 2, 1, 0]],
+                "route": [0, 2, 3],
+                "city": 1
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_route_remove_city() {
+        // The city is gone and the saved distance matches the distances of
+        // both routes.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = vec![0, 3, 1, 4, 2, 5];
+        let response = client
+            .post("/tsp/route/remove-city")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "route": route, "city": 1}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let shortened: Vec<usize> = serde_json::from_value(body["route"].clone()).unwrap();
+        assert_eq!(shortened, vec![0, 3, 4, 2, 5]);
+        assert_eq!(
+            body["delta"].as_f64().unwrap(),
+            tsp_solver::route_distance(&distances, &route)
+                - tsp_solver::route_distance(&distances, &shortened)
+        );
+
+        // Cities that are not visited cannot be removed.
+        let response = client
+            .post("/tsp/route/remove-city")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "route": shortened, "city": 1}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+
+        // Neither can cities of a matrix with a short row.
+        let response = client
+            .post("/tsp/route/remove-city")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 2], [1, 0], [2, 1, 0]],
+                "route": [0, 1, 2],
+                "city": 0
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_plateau() {
+        // The 6-city optimum is found long before 1000 generations end, and
+        // the plateau starts where the best distance stopped improving.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "n_generations": 1000,
+                "include": ["history"],
+                "seed": 1
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let meta = serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap())
+            .unwrap()
+            .meta;
+        assert!(meta.plateaued);
+        let plateau_generation = meta.plateau_generation.unwrap();
+        let history = meta.history.unwrap();
+        assert!(plateau_generation < meta.generations_run);
+        assert!(history[plateau_generation..]
+            .iter()
+            .all(|&distance| distance == 1248.0));
+
+        // A plateau that has to span all generations is never reached.
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 2], [1, 0, 1], [2, 1, 0]],
+                "n_generations": 10,
+                "plateau_fraction": 1.0
+            }))
+            .dispatch();
+        let meta = serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap())
+            .unwrap()
+            .meta;
+        assert!(!meta.plateaued);
+        assert_eq!(meta.plateau_generation, None);
+    }
+    #[test]
+    fn test_geo_itinerary() {
+        // A closed tour through n labeled points is described in n + 1 lines.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": [[52.52, 13.405], [48.8566, 2.3522], [50.1109, 8.6821]],
+                "labels": ["Berlin", "Paris", "Frankfurt"],
+                "itinerary": true,
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let itinerary: Vec<String> = serde_json::from_value(body["itinerary"].clone()).unwrap();
+        assert_eq!(itinerary.len(), 4);
+        let start = itinerary[0].strip_prefix("Start at ").unwrap();
+        assert!(itinerary[3].starts_with(&format!("Return to {} (", start)));
+        assert!(itinerary[1].starts_with("Go to ") && itinerary[1].ends_with(" km)"));
+
+        // Without labels there is no itinerary.
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": [[52.52, 13.405], [48.8566, 2.3522]],
+                "itinerary": true,
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_geo_itinerary_sort_worst() {
+        // The itinerary describes the best route, even if the response lists
+        // the routes worst-first.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let points = [
+            [52.52, 13.405],
+            [48.8566, 2.3522],
+            [50.1109, 8.6821],
+            [48.1351, 11.582],
+            [53.5511, 9.9937],
+            [50.9375, 6.9603],
+        ];
+        let labels = [
+            "Berlin",
+            "Paris",
+            "Frankfurt",
+            "Munich",
+            "Hamburg",
+            "Cologne",
+        ]
+        .map(String::from)
+        .to_vec();
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": points,
+                "labels": labels,
+                "itinerary": true,
+                "sort": "worst",
+                "n_generations": 1,
+                "top_n": 10,
+                "seed": 5
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let routes: Vec<RouteWithFitness> = serde_json::from_value(body["routes"].clone()).unwrap();
+        let best = routes.last().unwrap();
+        assert!(routes[0].fitness > best.fitness);
+        let distances = tsp_solver::haversine_matrix(&points).unwrap();
+        assert_eq!(
+            body["itinerary"],
+            json::json!(itinerary(&distances, &best.route, &labels))
+        );
+    }
+    #[test]
+    fn test_stress() {
+        // A tiny run reports its throughput and finishes shortly after the
+        // requested duration.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let before = time::Instant::now();
+        let response = client
+            .post("/tsp/stress")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"n": 5, "concurrency": 2, "duration_s": 0.2}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        assert!(before.elapsed() < time::Duration::from_secs(2));
+        let result: StressResult = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(result.throughput_per_s >= 0.0);
+        assert!(result.elapsed_s >= 0.2);
+        let latency = result.latency.unwrap();
+        assert!(latency.p50_ms <= latency.p90_ms && latency.p90_ms <= latency.p99_ms);
+
+        // Parameters are bounded.
+        let response = client
+            .post("/tsp/stress")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"n": 5, "concurrency": 1000, "duration_s": 0.2}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_output_representations() {
+        // Every route contains all requested representations.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 2, 1], [1, 0, 1, 2], [2, 1, 0, 1], [1, 2, 1, 0]],
+                "labels": ["a", "b", "c", "d"],
+                "output": ["indices", "labels"],
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let labels = ["a", "b", "c", "d"];
+        for route in body.routes {
+            let expected = route
+                .route
+                .iter()
+                .map(|&city| labels[city])
+                .collect::<Vec<&str>>();
+            assert_eq!(route.representations.labels.unwrap(), expected);
+            assert!(route.representations.coordinates.is_none());
+        }
+
+        // Coordinates are not available for distance matrices.
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1], [1, 0]],
+                "output": ["coordinates"],
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_matrix_normalize_to() {
+        // The shortest and longest off-diagonal distances hit the targets.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/normalize-to")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "min": 10,
+                "max": 20
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let normalized: Vec<Vec<f64>> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let off_diagonal = normalized
+            .iter()
+            .enumerate()
+            .flat_map(|(from, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(move |&(to, _)| to != from)
+                    .map(|(_, &distance)| distance)
+            })
+            .collect::<Vec<f64>>();
+        assert_eq!(
+            off_diagonal.iter().cloned().fold(f64::INFINITY, f64::min),
+            10.0
+        );
+        assert_eq!(off_diagonal.iter().cloned().fold(0.0, f64::max), 20.0);
+        assert!((0..6).all(|city| normalized[city][city] == 0.0));
+
+        // An inverted target range is not accepted.
+        let response = client
+            .post("/tsp/matrix/normalize-to")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": [[0, 1], [1, 0]], "min": 2, "max": 1}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_spatial_summary() {
+        // The bounding box contains all points and the centroid is their
+        // mean.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let points = [[52.52, 13.405], [48.8566, 2.3522], [50.1109, 8.6821]];
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": points,
+                "spatial_summary": true,
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let summary = body.spatial_summary.unwrap();
+        assert_eq!(summary.bbox, vec![48.8566, 2.3522, 52.52, 13.405]);
+        for point in points {
+            assert!(summary.bbox[0] <= point[0] && point[0] <= summary.bbox[2]);
+            assert!(summary.bbox[1] <= point[1] && point[1] <= summary.bbox[3]);
+        }
+        for axis in 0..2 {
+            let mean = points.iter().map(|point| point[axis]).sum::<f64>() / 3.0;
+            assert!((summary.centroid[axis] - mean).abs() < 1e-9);
+        }
+
+        // Distance matrices have no spatial extent.
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1], [1, 0]],
+                "spatial_summary": true,
+                "n_generations": 10
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_compute_lower_bound() {
+        // The lower bound and gap are only reported when requested.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let mut request = json::json!({
+            "distances": [
+                [0, 64, 378, 519, 434, 200],
+                [64, 0, 318, 455, 375, 164],
+                [378, 318, 0, 170, 265, 344],
+                [519, 455, 170, 0, 223, 428],
+                [434, 375, 265, 223, 0, 273],
+                [200, 164, 344, 428, 273, 0]
+            ],
+            "n_generations": 100
+        });
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&request)
+            .dispatch();
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(body["meta"].get("lower_bound").is_none());
+        assert!(body["meta"].get("gap_percent").is_none());
+
+        request["compute_lower_bound"] = json::json!(true);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&request)
+            .dispatch();
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(body.meta.lower_bound.unwrap() <= body.routes[0].fitness);
+        assert!(body.meta.gap_percent.unwrap() >= 0.0);
+    }
+    #[test]
+    fn test_solve_matrix_stream() {
+        // The 6-city matrix uploaded as CSV is solved to a valid route.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let csv = "0,64,378,519,434,200\n\
+                   64,0,318,455,375,164\n\
+                   378,318,0,170,265,344\n\
+                   519,455,170,0,223,428\n\
+                   434,375,265,223,0,273\n\
+                   200,164,344,428,273,0\n";
+        let response = client
+            .post("/tsp/solve-matrix-stream?n_generations=100&seed=3")
+            .header(http::ContentType::CSV)
+            .body(csv)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(tsp_solver::validate_route(&body.routes[0].route, 6).is_ok());
+        assert!(body.routes[0].fitness >= 1248.0);
+
+        // Rows of different length are rejected.
+        let response = client
+            .post("/tsp/solve-matrix-stream?n_generations=100")
+            .header(http::ContentType::CSV)
+            .body("0,1\n1,0,2\n")
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert_eq!(
+            response.into_string().unwrap(),
+            "\"Row 2 has 3 cells, but the first row has 2.\""
+        );
+    }
+    #[test]
+    fn test_polish() {
+        // The polished route admits neither an improving 2-opt nor Or-opt
+        // move.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let points = (0..12)
+            .map(|city| {
+                [
+                    f64::from((city * 37) % 101),
+                    f64::from((city * 53) % 97),
+                    0.0,
+                ]
+            })
+            .collect::<Vec<[f64; 3]>>();
+        let distances = tsp_solver::euclidean_matrix_3d(&points);
+        let route = (0..12).collect::<Vec<usize>>();
+        let response = client
+            .post("/tsp/polish")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "route": route}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let polished: PolishResult =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(tsp_solver::is_two_opt_optimal(&distances, &polished.route));
+        assert!(tsp_solver::best_or_opt_move(&distances, &polished.route).is_none());
+        assert!(polished.n_moves > 0);
+        assert!(
+            (tsp_solver::route_distance(&distances, &route)
+                - polished.distance
+                - polished.improvement)
+                .abs()
+                < 1e-9
+        );
+    }
+    #[test]
+    fn test_tsp_tie_break() {
+        // Two of the three tours through these cities have a distance of 8,
+        // and the one with the shorter longest leg is returned first.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 3, 5], [1, 0, 1, 3], [3, 1, 0, 1], [5, 3, 1, 0]],
+                "n_generations": 100,
+                "tie_break": "max_leg",
+                "seed": 0
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let distances = vec![
+            vec![0.0, 1.0, 3.0, 5.0],
+            vec![1.0, 0.0, 1.0, 3.0],
+            vec![3.0, 1.0, 0.0, 1.0],
+            vec![5.0, 3.0, 1.0, 0.0],
+        ];
+        assert_eq!(body.routes[0].fitness, 8.0);
+        assert!(tsp_solver::route_legs(&distances, &body.routes[0].route)
+            .iter()
+            .all(|&(_, _, distance)| distance <= 3.0));
+    }
+    #[test]
+    fn test_matrix_quantize() {
+        // All distances become multiples of the bucket size.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/quantize")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "bucket_size": 50
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let quantized: Vec<Vec<f64>> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(quantized
+            .iter()
+            .flatten()
+            .all(|distance| distance % 50.0 == 0.0));
+        assert_eq!(quantized[0][1], 50.0);
+        assert_eq!(quantized[1][1], 0.0);
+
+        let response = client
+            .post("/tsp/matrix/quantize")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": [[0, 1], [1, 0]], "bucket_size": 0}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_rank_legs() {
+        // The legs of the best route are ranked from the longest on with
+        // contiguous ranks.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "n_generations": 100,
+                "rank_legs": true
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let ranked = body.routes[0].ranked_legs.as_ref().unwrap();
+        assert_eq!(
+            ranked.iter().map(|leg| leg.rank).collect::<Vec<usize>>(),
+            vec![1, 2, 3, 4, 5, 6]
+        );
+        assert!(ranked.iter().all(|leg| leg.distance <= ranked[0].distance));
+        assert!(ranked
+            .windows(2)
+            .all(|pair| pair[0].distance >= pair[1].distance));
+        assert!(body.routes[1].ranked_legs.is_none());
+    }
+    #[test]
+    fn test_geo_default_generations() {
+        // The geo endpoint falls back to its own default and the matrix
+        // endpoint still requires n_generations.
+        let client = blocking::Client::tracked(build_rocket(ApiConfig {
+            max_cities: DEFAULT_MAX_CITIES,
+            max_exact_n: DEFAULT_MAX_EXACT_N,
+            max_tour_distance: DEFAULT_MAX_TOUR_DISTANCE,
+            max_meta_points: None,
+            default_generations: DefaultGenerations {
+                geo: Some(20),
+                ..DefaultGenerations::default()
+            },
+        }))
+        .unwrap();
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": [[52.52, 13.405], [48.8566, 2.3522], [50.1109, 8.6821]]
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body.meta.generations_run, 20);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": [[0, 1], [1, 0]]}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_track_lineage() {
+        // The lineage ends at the distance of the returned best route.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "n_generations": 100,
+                "track_lineage": true
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let lineage = body.meta.lineage.unwrap();
+        assert_eq!(lineage.last().unwrap().distance, body.routes[0].fitness);
+    }
+    #[test]
+    fn test_matrix_round_trip() {
+        // Simple and long decimal values come back unchanged.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/round-trip")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{"distances": [[0, 1.5, 0.1], [1.5, 0, 2.718281828459045], [0.1, 3e-7, 0]]}"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body, json::json!({"stable": true, "max_diff": 0.0}));
+    }
+    #[test]
+    fn test_evaluate_batch() {
+        // Duplicate routes reuse their distance, which is still correct.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let routes = vec![
+            vec![0, 1, 2, 3, 4, 5],
+            vec![0, 3, 1, 4, 2, 5],
+            vec![0, 1, 2, 3, 4, 5],
+            vec![0, 1, 2, 3, 4, 5],
+        ];
+        let response = client
+            .post("/tsp/evaluate/batch")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"distances": distances, "routes": routes}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let result: EvaluateBatchResult =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(result.cache_hits, 2);
+        assert_eq!(
+            result.distances,
+            routes
+                .iter()
+                .map(|route| tsp_solver::route_distance(&distances, route))
+                .collect::<Vec<f64>>()
+        );
+        assert_eq!(result.distances[0], 1248.0);
+    }
+    #[test]
+    fn test_route_segment() {
+        // The segment over the whole route plus the closing leg is the
+        // distance of the closed tour.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = vec![0, 3, 1, 4, 2, 5];
+        let segment = |start_index: usize, end_index: usize| {
+            client
+                .post("/tsp/route/segment")
+                .header(http::ContentType::JSON)
+                .json(&json::json!({
+                    "distances": distances,
+                    "route": route,
+                    "start_index": start_index,
+                    "end_index": end_index
+                }))
+                .dispatch()
+        };
+        let response = segment(0, 5);
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            body["distance"].as_f64().unwrap() + distances[5][0],
+            tsp_solver::route_distance(&distances, &route)
+        );
+        assert_eq!(segment(4, 6).status(), http::Status::BadRequest);
+        assert_eq!(segment(3, 2).status(), http::Status::BadRequest);
+        // A matrix with a short row is rejected.
+        let response = client
+            .post("/tsp/route/segment")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [[0, 1, 4], [1, 0], [4, 2, 0]],
+                "route": [0, 1, 2],
+                "start_index": 0,
+                "end_index": 2
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_distance_verified() {
+        // The reported fitness of a normal solve matches the recomputed
+        // distance of every route.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "distances": [
+                    [0, 64, 378, 519, 434, 200],
+                    [64, 0, 318, 455, 375, 164],
+                    [378, 318, 0, 170, 265, 344],
+                    [519, 455, 170, 0, 223, 428],
+                    [434, 375, 265, 223, 0, 273],
+                    [200, 164, 344, 428, 273, 0]
+                ],
+                "n_generations": 100,
+                "canonical": true
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(body.meta.distance_verified);
+    }
+    #[test]
+    fn test_matrix_from_undirected_edges() {
+        // The matrix equals its transpose and missing pairs are the sentinel.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/from-edges-weighted-undirected")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "n": 4,
+                "edges": [[0, 1, 5.0], [1, 2, 3.0], [3, 0, 1.5], [2, 1, 3.0]],
+                "sentinel": -1
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let distances: Vec<Vec<f64>> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(tsp_solver::is_symmetric(&distances, 0.0));
+        assert_eq!(distances[0][3], 1.5);
+        assert_eq!(distances[0][2], -1.0);
+        assert_eq!(distances[1][3], -1.0);
+
+        // The same pair cannot have two different weights.
+        let response = client
+            .post("/tsp/matrix/from-edges-weighted-undirected")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"n": 2, "edges": [[0, 1, 1.0], [1, 0, 2.0]], "sentinel": -1}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+
+        // More cities than the server supports are rejected before the
+        // matrix is allocated.
+        let response = client
+            .post("/tsp/matrix/from-edges-weighted-undirected")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({"n": 1_000_000_000, "edges": [], "sentinel": -1}))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_random_baseline() {
+        // The solver beats the mean random tour.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = r##"[
+            [0,64,378,519,434,200],
+            [64,0,318,455,375,164],
+            [378,318,0,170,265,344],
+            [519,455,170,0,223,428],
+            [434,375,265,223,0,273],
+            [200,164,344,428,273,0]]"##;
+        let response = client
+            .post("/tsp/random-baseline")
+            .header(http::ContentType::JSON)
+            .body(format!(
+                r##"{{"distances": {}, "samples": 200, "seed": 3}}"##,
+                distances
+            ))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let baseline: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let mean = baseline["mean"].as_f64().unwrap();
+        assert!(baseline["min"].as_f64().unwrap() <= mean);
+        assert!(mean <= baseline["max"].as_f64().unwrap());
+        assert!(baseline["std"].as_f64().unwrap() > 0.0);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(format!(
+                r##"{{"distances": {}, "n_generations": 100, "seed": 3}}"##,
+                distances
+            ))
+            .dispatch();
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(solution.routes[0].fitness < mean);
+        // Zero samples are rejected.
+        let response = client
+            .post("/tsp/random-baseline")
+            .header(http::ContentType::JSON)
+            .body(format!(r##"{{"distances": {}, "samples": 0}}"##, distances))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_huge_distances() {
+        // Distances whose tours overflow are rejected instead of solved to
+        // an infinite fitness, also below a configured limit.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{"distances": [[0,1e308,1e308],[1e308,0,1e308],[1e308,1e308,0]], "n_generations": 10}"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert!(response.into_string().unwrap().contains("normalize"));
+        let client = blocking::Client::tracked(build_rocket(ApiConfig {
+            max_cities: DEFAULT_MAX_CITIES,
+            max_exact_n: DEFAULT_MAX_EXACT_N,
+            max_tour_distance: 5.0,
+            max_meta_points: None,
+            default_generations: DefaultGenerations::default(),
+        }))
+        .unwrap();
+        let solve = |body: &str| {
+            client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(body)
+                .dispatch()
+                .status()
+        };
+        assert_eq!(
+            solve(r##"{"distances": [[0,1,2],[1,0,2],[2,2,0]], "n_generations": 10}"##),
+            http::Status::BadRequest
+        );
+        assert_eq!(
+            solve(r##"{"distances": [[0,1,1],[1,0,1],[1,1,0]], "n_generations": 10}"##),
+            http::Status::Ok
+        );
+    }
+    #[test]
+    fn test_tsp_reverse() {
+        // The reverse routes are scored on the transposed matrix, which is
+        // the cost of driving them backwards on the original one.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = vec![
+            vec![0.0, 1.0, 9.0, 4.0],
+            vec![7.0, 0.0, 2.0, 8.0],
+            vec![3.0, 6.0, 0.0, 1.0],
+            vec![2.0, 5.0, 7.0, 0.0],
+        ];
+        let response = client
+            .post("/tsp/reverse")
+            .header(http::ContentType::JSON)
+            .body(json::json!({"distances": distances, "n_generations": 50, "seed": 1}).to_string())
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let best = &solution.routes[0];
+        let transposed = tsp_solver::transpose(&distances);
+        assert_eq!(
+            best.fitness,
+            tsp_solver::route_distance(&transposed, &best.route)
+        );
+        let backwards = best.route.iter().rev().cloned().collect::<Vec<usize>>();
+        assert_eq!(
+            best.fitness,
+            tsp_solver::route_distance(&distances, &backwards)
+        );
+    }
+    #[test]
+    fn test_tsp_echo_input() {
+        // The echo reports the defaults that filled the missing parameters
+        // and only contains the matrix on request.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let solve = |echo_matrix: bool| {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(
+                    json::json!({
+                        "distances": [[0,1,2],[1,0,3],[2,3,0]],
+                        "n_generations": 12,
+                        "echo_input": true,
+                        "echo_matrix": echo_matrix
+                    })
+                    .to_string(),
+                )
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let solution: SolveTspResponse =
+                serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            solution.meta.input.unwrap()
+        };
+        let echo = solve(false);
+        assert_eq!(echo.n_cities, 3);
+        assert_eq!(echo.parameters.n_generations, Some(12));
+        let defaults = tsp_solver::SolverConfig::default();
+        assert_eq!(echo.parameters.n_routes, Some(defaults.n_routes));
+        assert_eq!(echo.parameters.two_opt, Some(false));
+        assert!(echo.distances.is_none());
+        assert_eq!(solve(true).distances.unwrap()[2], vec![2.0, 3.0, 0.0]);
+    }
+    #[test]
+    fn test_route_grade() {
+        // The optimal route of the 6-city matrix, also rotated, is graded as
+        // optimal and a detour as poor.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let grade = |route: &str| {
+            let response = client
+                .post("/tsp/route/grade")
+                .header(http::ContentType::JSON)
+                .body(format!(
+                    r##"{{
+                    "distances": [
+                        [0,64,378,519,434,200],
+                        [64,0,318,455,375,164],
+                        [378,318,0,170,265,344],
+                        [519,455,170,0,223,428],
+                        [434,375,265,223,0,273],
+                        [200,164,344,428,273,0]],
+                    "route": {}
+                    }}"##,
+                    route
+                ))
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            serde_json::from_str::<GradeResult>(&response.into_string().unwrap()).unwrap()
+        };
+        let optimal = grade("[0,1,2,3,4,5]");
+        assert_eq!(optimal.grade, Grade::Optimal);
+        assert_eq!(optimal.optimal_distance, 1248.0);
+        assert_eq!(optimal.gap_percent, 0.0);
+        assert_eq!(grade("[3,4,5,0,1,2]").grade, Grade::Optimal);
+        let detour = grade("[0,3,1,4,2,5]");
+        assert_eq!(detour.grade, Grade::Poor);
+        assert!(detour.gap_percent > GOOD_GRADE_MAX_GAP_PERCENT);
+    }
+    #[test]
+    fn test_tsp_max_meta_points() {
+        // The per-generation arrays are capped by the server and the cap can
+        // be overridden per request.
+        let client = blocking::Client::tracked(build_rocket(ApiConfig {
+            max_cities: DEFAULT_MAX_CITIES,
+            max_exact_n: DEFAULT_MAX_EXACT_N,
+            max_tour_distance: DEFAULT_MAX_TOUR_DISTANCE,
+            max_meta_points: Some(10),
+            default_generations: DefaultGenerations::default(),
+        }))
+        .unwrap();
+        let solve = |max_meta_points: Option<usize>| {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(
+                    json::json!({
+                        "distances": [[0,1,2],[1,0,3],[2,3,0]],
+                        "n_generations": 200,
+                        "include": ["history", "schedule"],
+                        "max_meta_points": max_meta_points
+                    })
+                    .to_string(),
+                )
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let solution: SolveTspResponse =
+                serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            solution.meta
+        };
+        let capped = solve(None);
+        assert_eq!(capped.history.unwrap().len(), 10);
+        let schedule = capped.schedule.unwrap();
+        assert_eq!(schedule.len(), 10);
+        assert_eq!(schedule[0], 1.0);
+        let overridden = solve(Some(50));
+        assert_eq!(overridden.history.unwrap().len(), 50);
+        assert_eq!(overridden.schedule.unwrap().len(), 50);
+    }
+    #[test]
+    fn test_warm_pool() {
+        // A solve of a warmed size uses a pooled population until the pool
+        // is empty.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/warm-pool")
+            .header(http::ContentType::JSON)
+            .body(r##"{"n": 4, "n_routes": 10, "count": 1}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let warmed: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(warmed["pooled"], 1);
+        let solve = || {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(
+                    r##"{"distances": [[0,1,2,3],[1,0,4,5],[2,4,0,6],[3,5,6,0]], "n_generations": 10, "n_routes": 10}"##,
+                )
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let solution: SolveTspResponse =
+                serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            solution.meta.population_from_pool
+        };
+        assert!(solve());
+        assert!(!solve());
+        // A seeded solve generates its own population and leaves the pool
+        // untouched.
+        let warm = |count: usize| {
+            client
+                .post("/tsp/warm-pool")
+                .header(http::ContentType::JSON)
+                .body(json::json!({"n": 4, "n_routes": 10, "count": count}).to_string())
+                .dispatch()
+        };
+        assert_eq!(warm(1).status(), http::Status::Ok);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{"distances": [[0,1,2,3],[1,0,4,5],[2,4,0,6],[3,5,6,0]], "n_generations": 10, "n_routes": 10, "seed": 3}"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let seeded: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert!(!seeded.meta.population_from_pool);
+        assert!(solve());
+        // The pooled solve reports the seed of its population, which
+        // reproduces it without the pool.
+        let solve_with = |body: json::Value| {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(body.to_string())
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).unwrap()
+        };
+        let response = client
+            .post("/tsp/warm-pool")
+            .header(http::ContentType::JSON)
+            .body(r##"{"n": 8, "n_routes": 10, "count": 1}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let points = (0..8)
+            .map(|city| [f64::from((city * 37) % 11), f64::from((city * 53) % 7), 0.0])
+            .collect::<Vec<[f64; 3]>>();
+        let body = json::json!({
+            "distances": tsp_solver::euclidean_matrix_3d(&points),
+            "n_generations": 10,
+            "n_routes": 10,
+            "top_n": 5
+        });
+        let pooled = solve_with(body.clone());
+        assert!(pooled.meta.population_from_pool);
+        let mut reseeded_body = body;
+        reseeded_body["seed"] = json::json!(pooled.meta.seed);
+        let reseeded = solve_with(reseeded_body);
+        assert!(!reseeded.meta.population_from_pool);
+        assert_eq!(
+            reseeded
+                .routes
+                .iter()
+                .map(|route| &route.route)
+                .collect::<Vec<_>>(),
+            pooled
+                .routes
+                .iter()
+                .map(|route| &route.route)
+                .collect::<Vec<_>>()
+        );
+        // The pool of one size is capped.
+        assert_eq!(warm(WARM_POOL_MAX_POOLED).status(), http::Status::Ok);
+        assert_eq!(warm(1).status(), http::Status::BadRequest);
+        // Sizes outside the bounds are rejected.
+        let response = client
+            .post("/tsp/warm-pool")
+            .header(http::ContentType::JSON)
+            .body(r##"{"n": 4, "n_routes": 10, "count": 0}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_bottleneck() {
+        // The shortest tour 0-1-2-3 has a leg of 10, the bottleneck-optimal
+        // tour 0-1-3-2 is longer but its longest leg is only 6.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = vec![
+            vec![0.0, 1.0, 6.0, 10.0],
+            vec![1.0, 0.0, 1.0, 6.0],
+            vec![6.0, 1.0, 0.0, 1.0],
+            vec![10.0, 6.0, 1.0, 0.0],
+        ];
+        let shortest = tsp_solver::brute_force_route(&distances);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                json::json!({
+                    "distances": distances,
+                    "n_generations": 20,
+                    "objective": "bottleneck",
+                    "seed": 2
+                })
+                .to_string(),
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let bottleneck = tsp_solver::bottleneck_distance(&distances, &solution.routes[0].route);
+        assert!(bottleneck <= tsp_solver::bottleneck_distance(&distances, &shortest));
+        // The objective value is reported next to the total distance.
+        assert_eq!(solution.meta.objective, tsp_solver::Objective::Bottleneck);
+        assert_eq!(solution.routes[0].bottleneck, Some(bottleneck));
+        assert_eq!(
+            solution.routes[0].fitness,
+            tsp_solver::route_distance(&distances, &solution.routes[0].route)
+        );
+        assert_eq!(bottleneck, 6.0);
+    }
+    #[test]
+    fn test_matrix_zero_diagonal() {
+        // Non-zero and missing diagonal entries become zero, all others are
+        // kept.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/zero-diagonal")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[7,1,2],[1,null,3],[2,3,0]]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let zeroed: Vec<Vec<f64>> = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            zeroed,
+            vec![
+                vec![0.0, 1.0, 2.0],
+                vec![1.0, 0.0, 3.0],
+                vec![2.0, 3.0, 0.0]
+            ]
+        );
+        // Missing entries off the diagonal are rejected.
+        let response = client
+            .post("/tsp/matrix/zero-diagonal")
+            .header(http::ContentType::JSON)
+            .body(r##"{"distances": [[0,null],[1,0]]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+        // `/tsp` zeroes the diagonal before solving on request.
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{"distances": [[-1,1,2],[1,0,3],[2,3,0]], "n_generations": 10, "auto_zero_diagonal": true}"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+    }
     #[test]
-    fn test_not_found() {
-        // Test that for an unkown route, "Not found" is returned
+    fn test_tsp_track_exploration() {
+        // Every generation evaluates at most n_routes * (n_routes - 1)
+        // offspring, their parents and the injected random routes.
         let client = blocking::Client::tracked(rocket()).unwrap();
-        let response = client.get("/does/not/exist").dispatch();
-        assert_eq!(response.status(), http::Status { code: 404 });
-        assert_eq!(response.content_type(), Some(http::ContentType::JSON));
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 20,
+                "n_routes": 10,
+                "n_random_individuals_per_generation": 5,
+                "track_exploration": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let explored = solution.meta.unique_tours_explored.unwrap();
+        assert!(explored > 0);
+        assert!(explored <= 10 + 20 * (10 * 10 + 5));
+        // There are only 5! / 2 = 60 distinct tours through 6 cities.
+        assert!(explored <= 60);
+    }
+    #[test]
+    fn test_tsp_candidate() {
+        // The refined routes are valid and candidates out of range are
+        // rejected.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let solve = |candidates: &str| {
+            client
+                .post("/tsp/candidate")
+                .header(http::ContentType::JSON)
+                .body(format!(
+                    r##"{{
+                    "distances": [
+                        [0,64,378,519,434,200],
+                        [64,0,318,455,375,164],
+                        [378,318,0,170,265,344],
+                        [519,455,170,0,223,428],
+                        [434,375,265,223,0,273],
+                        [200,164,344,428,273,0]],
+                    "candidates": {},
+                    "n_generations": 5,
+                    "seed": 4
+                    }}"##,
+                    candidates
+                ))
+                .dispatch()
+        };
+        let response = solve("[[0, [1, 5]], [1, [2]], [2, [3]], [3, [4]], [4, [5]]]");
+        assert_eq!(response.status(), http::Status::Ok);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let routes: Vec<RouteWithFitness> = serde_json::from_value(body["routes"].clone()).unwrap();
+        // The refinement only adds legs between candidates to the routes of
+        // the genetic algorithm.
+        let distances = serde_json::from_value::<Vec<Vec<f64>>>(json::json!([
+            [0, 64, 378, 519, 434, 200],
+            [64, 0, 318, 455, 375, 164],
+            [378, 318, 0, 170, 265, 344],
+            [519, 455, 170, 0, 223, 428],
+            [434, 375, 265, 223, 0, 273],
+            [200, 164, 344, 428, 273, 0]
+        ]))
+        .unwrap();
+        let legs = |route: &[usize]| {
+            tsp_solver::route_legs(&distances, route)
+                .into_iter()
+                .map(|(from, to, _)| (from.min(to), from.max(to)))
+                .collect::<std::collections::HashSet<(usize, usize)>>()
+        };
+        let solver_config = SolverParameters {
+            n_generations: Some(5),
+            seed: Some(4),
+            ..SolverParameters::default()
+        }
+        .to_config()
+        .unwrap();
+        // The candidates are exactly the legs of the tour 0-1-2-3-4-5.
+        let mut allowed = legs(&[0, 1, 2, 3, 4, 5]);
+        for genetic in tsp_solver::solve_tsp_core(&distances, &solver_config).routes {
+            allowed.extend(legs(&genetic.indexes));
+        }
+        for route in routes {
+            assert!(tsp_solver::validate_route(&route.route, 6).is_ok());
+            assert!(legs(&route.route).is_subset(&allowed));
+        }
+        assert_eq!(solve("[[0, [6]]]").status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_output_permutation_matrix() {
+        // Every position visits one city and every city is visited once.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{"distances": [[0,1,2,3],[1,0,4,5],[2,4,0,6],[3,5,6,0]], "n_generations": 10, "output": ["permutation_matrix"]}"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let best = &solution.routes[0];
+        let matrix = best.representations.permutation_matrix.as_ref().unwrap();
+        assert!(matrix.iter().all(|row| row.iter().sum::<u8>() == 1));
+        assert!((0..4).all(|city| matrix.iter().map(|row| row[city]).sum::<u8>() == 1));
+        assert_eq!(matrix[0][best.route[0]], 1);
+    }
+    #[test]
+    fn test_solve_and_refine() {
+        // Every stage starts from the route of the previous one, so the
+        // distance never increases.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let refine = |pipeline: &str| {
+            client
+                .post("/tsp/solve-and-refine")
+                .header(http::ContentType::JSON)
+                .body(format!(
+                    r##"{{
+                    "distances": [
+                        [0,64,378,519,434,200],
+                        [64,0,318,455,375,164],
+                        [378,318,0,170,265,344],
+                        [519,455,170,0,223,428],
+                        [434,375,265,223,0,273],
+                        [200,164,344,428,273,0]],
+                    "pipeline": {},
+                    "n_generations": 10,
+                    "seed": 2
+                    }}"##,
+                    pipeline
+                ))
+                .dispatch()
+        };
+        for pipeline in [r#"["nn_seed", "2-opt"]"#, r#"["genetic", "or-opt"]"#] {
+            let response = refine(pipeline);
+            assert_eq!(response.status(), http::Status::Ok);
+            let result: SolveAndRefineResult =
+                serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            let stages = &result.meta.stages;
+            assert_eq!(stages.len(), 2);
+            assert!(stages[1].distance <= stages[0].distance);
+            assert_eq!(result.distance, stages[1].distance);
+            assert!(tsp_solver::validate_route(&result.route, 6).is_ok());
+        }
+        // The pipeline has to build a route before refining it.
+        assert_eq!(refine(r#"["2-opt"]"#).status(), http::Status::BadRequest);
         assert_eq!(
-            response.into_string().unwrap(),
-            String::from(r##""Not found!""##)
+            refine(r#"["genetic", "nn_seed"]"#).status(),
+            http::Status::BadRequest
+        );
+        assert_eq!(
+            refine(r#"["3-opt"]"#).status(),
+            http::Status::UnprocessableEntity
         );
     }
     #[test]
-    fn test_liveness() {
-        // Test the liveness probe.
+    fn test_tsp_group_by_distance() {
+        // The groups cover the whole population and the first one holds
+        // the best route.
         let client = blocking::Client::tracked(rocket()).unwrap();
-        let response = client.get("/alive").dispatch();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 20,
+                "n_routes": 12,
+                "group_by_distance": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let groups = solution.meta.distance_groups.unwrap();
+        assert_eq!(groups[0].distance, solution.routes[0].fitness);
+        assert_eq!(groups.iter().map(|group| group.count).sum::<usize>(), 12);
+        assert!(groups
+            .windows(2)
+            .all(|pair| pair[0].distance < pair[1].distance));
+        assert!(groups
+            .iter()
+            .all(|group| group.routes.len() == group.count.min(GROUP_MAX_REPRESENTATIVES)));
+    }
+    #[test]
+    fn test_tsp_rng_reproducible() {
+        // The same seed gives the same routes and the pinned algorithm is
+        // reported.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let solve = || {
+            let response = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(
+                    r##"{
+                    "distances": [
+                        [0,64,378,519,434,200],
+                        [64,0,318,455,375,164],
+                        [378,318,0,170,265,344],
+                        [519,455,170,0,223,428],
+                        [434,375,265,223,0,273],
+                        [200,164,344,428,273,0]],
+                    "n_generations": 5,
+                    "top_n": 5,
+                    "seed": 11
+                    }"##,
+                )
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).unwrap()
+        };
+        let first = solve();
+        let second = solve();
+        assert_eq!(first.meta.rng, "chacha8");
+        let routes = |solution: &SolveTspResponse| {
+            solution
+                .routes
+                .iter()
+                .map(|route| route.route.clone())
+                .collect::<Vec<Vec<usize>>>()
+        };
+        assert_eq!(routes(&first), routes(&second));
+    }
+    #[test]
+    fn test_matrix_upper_triangle() {
+        // The triangle of a symmetric matrix rebuilds it and an asymmetric
+        // matrix is warned about.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let triangle = |distances: &str| {
+            let response = client
+                .post("/tsp/matrix/upper-triangle")
+                .header(http::ContentType::JSON)
+                .body(format!(r##"{{"distances": {}}}"##, distances))
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            serde_json::from_str::<json::Value>(&response.into_string().unwrap()).unwrap()
+        };
+        let symmetric = triangle("[[0,1,2],[1,0,3],[2,3,0]]");
+        assert_eq!(symmetric["warnings"], json::json!([]));
+        let rows: Vec<Vec<f64>> = serde_json::from_value(symmetric["triangle"].clone()).unwrap();
+        assert_eq!(
+            tsp_solver::from_upper_triangle(&rows).unwrap(),
+            vec![
+                vec![0.0, 1.0, 2.0],
+                vec![1.0, 0.0, 3.0],
+                vec![2.0, 3.0, 0.0]
+            ]
+        );
+        let asymmetric = triangle("[[0,1],[2,0]]");
+        assert_eq!(asymmetric["warnings"].as_array().unwrap().len(), 1);
+    }
+    #[test]
+    fn test_geo_gpx() {
+        // The GPX track visits the points in tour order and returns to the
+        // first one.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let body = r##"{
+            "points": [[52.52, 13.405], [48.137, 11.575], [50.110, 8.682], [53.551, 9.994]],
+            "n_generations": 10,
+            "seed": 3
+            }"##;
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .body(body)
+            .dispatch();
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .header(http::Header::new("Accept", "application/gpx+xml"))
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        assert_eq!(
+            response.content_type(),
+            Some(http::ContentType::new("application", "gpx+xml"))
+        );
+        let gpx = response.into_string().unwrap();
+        let points = [
+            [52.52, 13.405],
+            [48.137, 11.575],
+            [50.110, 8.682],
+            [53.551, 9.994],
+        ];
+        let expected = solution.routes[0]
+            .route
+            .iter()
+            .chain(solution.routes[0].route.first())
+            .map(|&city| {
+                format!(
+                    r#"<trkpt lat="{}" lon="{}"/>"#,
+                    points[city][0], points[city][1]
+                )
+            })
+            .collect::<Vec<String>>();
+        let trackpoints = gpx
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("<trkpt"))
+            .collect::<Vec<&str>>();
+        assert_eq!(trackpoints, expected);
+    }
+    #[test]
+    fn test_geo_gpx_sort_worst() {
+        // The GPX track is the best route, even if the response lists the
+        // routes worst-first.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let points = [
+            [52.52, 13.405],
+            [48.8566, 2.3522],
+            [50.1109, 8.6821],
+            [48.1351, 11.582],
+            [53.5511, 9.9937],
+            [50.9375, 6.9603],
+        ];
+        let body = json::json!({
+            "points": points,
+            "sort": "worst",
+            "n_generations": 1,
+            "top_n": 10,
+            "seed": 5
+        });
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&body)
+            .dispatch();
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let best = solution.routes.last().unwrap();
+        assert!(solution.routes[0].fitness > best.fitness);
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .header(http::Header::new("Accept", "application/gpx+xml"))
+            .json(&body)
+            .dispatch();
         assert_eq!(response.status(), http::Status::Ok);
-        assert_eq!(response.content_type(), Some(http::ContentType::JSON));
         assert_eq!(
             response.into_string().unwrap(),
-            String::from(r##""alive""##)
+            tsp_solver::route_to_gpx(&best.route, &points)
         );
     }
     #[test]
-    fn test_tsp() {
-        // Check that the enpoint works with a realistic request.
-        // I make the following assumptions on the reponse:
-        // - 200 status code
-        // - Return type is json
-        // - Return value can be deserialized into Vec<RouteWithFitness>
-        // - There are three solutions returned.
+    fn test_tsp_strict() {
+        // A non-zero diagonal is a warning, which strict mode rejects.
         let client = blocking::Client::tracked(rocket()).unwrap();
+        let solve = |strict: bool| {
+            client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(
+                    json::json!({
+                        "distances": [[0,1,2],[1,5,3],[2,3,0]],
+                        "n_generations": 10,
+                        "strict": strict
+                    })
+                    .to_string(),
+                )
+                .dispatch()
+        };
+        let response = solve(false);
+        assert_eq!(response.status(), http::Status::Ok);
+        let solution: SolveTspResponse =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(solution.meta.warnings.len(), 1);
+        let response = solve(true);
+        assert_eq!(response.status(), http::Status::BadRequest);
+        assert!(response.into_string().unwrap().contains("itself"));
+        // Duplicate points of coordinate inputs are listed as well.
         let response = client
-            .post("/tsp")
+            .post("/tsp/coordinates/3d")
+            .header(http::ContentType::JSON)
+            .body(r##"{"points": [[0,0,0],[1,0,0],[0,0,0]], "n_generations": 10, "strict": true}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_open_path() {
+        // The path runs from start to end through all original cities.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/open-path")
             .header(http::ContentType::JSON)
             .body(
                 r##"{
@@ -128,15 +5744,473 @@ mod test {
                     [519,455,170,0,223,428],
                     [434,375,265,223,0,273],
                     [200,164,344,428,273,0]],
-                "n_generations":  10000
+                "start": 1,
+                "end": 4,
+                "n_generations": 50,
+                "seed": 0
                 }"##,
             )
             .dispatch();
-
         assert_eq!(response.status(), http::Status::Ok);
+        let path: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let route: Vec<usize> = serde_json::from_value(path["route"].clone()).unwrap();
+        assert_eq!(route.first(), Some(&1));
+        assert_eq!(route.last(), Some(&4));
+        assert!(tsp_solver::validate_route(&route, 6).is_ok());
+        let distances = [
+            [0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            [64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            [378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            [519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            [434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            [200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let distance = route
+            .windows(2)
+            .map(|leg| distances[leg[0]][leg[1]])
+            .sum::<f64>();
+        assert_eq!(path["distance"].as_f64().unwrap(), distance);
+    }
+    #[test]
+    fn test_debug_header() {
+        // A request can ask for debug logs without changing the response,
+        // other requests write none.
+        let buffer = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let client =
+            blocking::Client::tracked(build_rocket(ApiConfig::from_env()).manage(LogSink {
+                writer: buffer.clone(),
+            }))
+            .unwrap();
+        let solve = |header: Option<&str>| {
+            let mut request = client
+                .post("/tsp")
+                .header(http::ContentType::JSON)
+                .body(r##"{"distances": [[0,1,2],[1,0,3],[2,3,0]], "n_generations": 10}"##);
+            if let Some(value) = header {
+                request = request.header(http::Header::new(DEBUG_HEADER, value.to_owned()));
+            }
+            let response = request.dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            assert!(
+                serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).is_ok()
+            );
+            String::from_utf8(std::mem::take(&mut *buffer.lock().unwrap())).unwrap()
+        };
+        let logged = solve(Some("true"));
+        assert!(logged.contains("[debug] Solving a 3x3 distance matrix."));
+        assert!(logged.contains("[debug] Computation took "));
+        // Other requests stay quiet.
+        assert!(solve(None).is_empty());
+        assert!(solve(Some("false")).is_empty());
+        // Only an explicit `true` raises the log level.
+        let debug = |value| RequestLog::from_header(value, LogSink::default()).debug;
+        assert!(debug(Some("true")));
+        assert!(debug(Some(" TRUE ")));
+        assert!(!debug(Some("1")));
+        assert!(!debug(None));
+    }
+    #[test]
+    fn test_route_delta() {
+        // The delta of a swap is the difference of the two full distances.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/route/delta")
+            .header(http::ContentType::JSON)
+            .body(
+                json::json!({
+                    "distances": distances,
+                    "route": [0, 2, 1, 3, 4, 5],
+                    "edit": {"type": "swap", "i": 1, "j": 2},
+                })
+                .to_string(),
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let result: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(result["route"], json::json!([0, 1, 2, 3, 4, 5]));
+        let difference = tsp_solver::route_distance(&distances, &[0, 1, 2, 3, 4, 5])
+            - tsp_solver::route_distance(&distances, &[0, 2, 1, 3, 4, 5]);
+        assert!((result["delta"].as_f64().unwrap() - difference).abs() < 1e-9);
+    }
+    #[test]
+    fn test_generate_suite() {
+        // Every reported optimum is the brute-force optimum of its instance,
+        // and the suite only depends on the seed.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let generate = || {
+            let response = client
+                .post("/tsp/generate-suite")
+                .header(http::ContentType::JSON)
+                .body(r##"{"count": 3, "n": 6, "seed": 7}"##)
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            serde_json::from_str::<json::Value>(&response.into_string().unwrap()).unwrap()
+        };
+        let suite = generate();
+        let instances: Vec<SuiteInstance> =
+            serde_json::from_value(suite["instances"].clone()).unwrap();
+        assert_eq!(instances.len(), 3);
+        for instance in instances.iter() {
+            assert_eq!(instance.distances.len(), 6);
+            let optimum = tsp_solver::brute_force_route(&instance.distances);
+            assert_eq!(
+                instance.optimal_distance,
+                tsp_solver::route_distance(&instance.distances, &optimum)
+            );
+            assert_eq!(
+                instance.optimal_distance,
+                tsp_solver::route_distance(&instance.distances, &instance.optimal_route)
+            );
+        }
+        assert_eq!(generate(), suite);
+        let response = client
+            .post("/tsp/generate-suite")
+            .header(http::ContentType::JSON)
+            .body(r##"{"count": 1, "n": 12, "seed": 7}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    /// Test seam: a solve-endpoint whose solver always panics.
+    #[post("/tsp/panicking-solve")]
+    fn panicking_solve() -> Result<json::Value, SolveError> {
+        guard_solve(|| -> json::Value { panic!("Deliberate panic of the solver.") })
+    }
+
+    #[test]
+    fn test_solver_panic() {
+        // A panicking solve yields the JSON 500 with a stable code, is
+        // counted, and leaves the server able to answer further requests.
+        let client = blocking::Client::tracked(
+            build_rocket(ApiConfig::from_env()).mount("/", routes![panicking_solve]),
+        )
+        .unwrap();
+        let before = SOLVER_PANICS.load(atomic::Ordering::Relaxed);
+        let response = client.post("/tsp/panicking-solve").dispatch();
+        assert_eq!(response.status(), http::Status::InternalServerError);
         assert_eq!(response.content_type(), Some(http::ContentType::JSON));
-        let returned_routes: Vec<RouteWithFitness> =
-            serde_json::from_str(&response.into_string().unwrap()).unwrap();
-        assert_eq!(returned_routes.len(), 3);
+        let body: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["code"], SOLVER_PANIC_CODE);
+        assert_eq!(body["message"], FAILED_COMPUTATION_MESSAGE);
+        assert!(SOLVER_PANICS.load(atomic::Ordering::Relaxed) > before);
+        let metrics: json::Value =
+            serde_json::from_str(&client.get("/metrics").dispatch().into_string().unwrap())
+                .unwrap();
+        assert!(metrics["solver_panics"].as_u64().unwrap() > before as u64);
+        assert_eq!(client.get("/alive").dispatch().status(), http::Status::Ok);
+    }
+    #[test]
+    fn test_matrix_neighborhood_size() {
+        // The 2-opt neighborhood of 10 cities has 10 * 7 / 2 moves, each
+        // looking up 4 distances.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/matrix/neighborhood-size")
+            .header(http::ContentType::JSON)
+            .body(r##"{"n": 10, "k": 2}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let size: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(size["neighbors"], 35);
+        assert_eq!(size["lookups_per_pass"], 140.0);
+        let response = client
+            .post("/tsp/matrix/neighborhood-size")
+            .header(http::ContentType::JSON)
+            .body(r##"{"n": 10, "k": 9}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_geo_report_metrics() {
+        // The best route is scored under both metrics in kilometers, which
+        // nearly agree for cities this close to each other.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "points": [[52.52, 13.40], [48.14, 11.58], [50.94, 6.96], [53.55, 9.99]],
+                "report_metrics": ["haversine", "euclidean"],
+                "n_generations": 20
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solved: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let haversine = solved["metric_distances"]["haversine"].as_f64().unwrap();
+        let euclidean = solved["metric_distances"]["euclidean"].as_f64().unwrap();
+        assert!((haversine - solved["routes"][0]["fitness"].as_f64().unwrap()).abs() < 1e-9);
+        let best: Vec<usize> =
+            serde_json::from_value(solved["routes"][0]["route"].clone()).unwrap();
+        let projected = tsp_solver::euclidean_matrix_3d(&tsp_solver::equirectangular_points(&[
+            [52.52, 13.40],
+            [48.14, 11.58],
+            [50.94, 6.96],
+            [53.55, 9.99],
+        ]));
+        assert!((euclidean - tsp_solver::route_distance(&projected, &best)).abs() < 1e-9);
+        assert!((haversine - euclidean).abs() < 0.01 * haversine);
+
+        // The metrics score the best route, even if the response lists the
+        // routes worst-first.
+        let response = client
+            .post("/tsp/geo")
+            .header(http::ContentType::JSON)
+            .json(&json::json!({
+                "points": [
+                    [52.52, 13.405],
+                    [48.8566, 2.3522],
+                    [50.1109, 8.6821],
+                    [48.1351, 11.582],
+                    [53.5511, 9.9937],
+                    [50.9375, 6.9603]
+                ],
+                "report_metrics": ["haversine"],
+                "sort": "worst",
+                "n_generations": 1,
+                "top_n": 10,
+                "seed": 5
+            }))
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solved: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let routes: Vec<RouteWithFitness> =
+            serde_json::from_value(solved["routes"].clone()).unwrap();
+        let best = routes.last().unwrap();
+        assert!(routes[0].fitness > best.fitness);
+        let haversine = solved["metric_distances"]["haversine"].as_f64().unwrap();
+        assert!((haversine - best.fitness).abs() < 1e-9);
+    }
+    #[test]
+    fn test_target_gap_percent() {
+        // A loose target gap on a euclidean matrix stops the run early.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let distances = tsp_solver::euclidean_matrix_3d(&[
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [1.0, 2.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [-1.0, 1.0, 0.0],
+        ]);
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                json::json!({
+                    "distances": distances,
+                    "n_generations": 1000,
+                    "target_gap_percent": 100.0,
+                    "seed": 3,
+                })
+                .to_string(),
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solved =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).unwrap();
+        assert_eq!(solved.meta.stop_reason, tsp_solver::StopReason::TargetGap);
+        assert!(solved.meta.generations_run < 1000);
+        let bound = tsp_solver::mst_lower_bound(&distances);
+        assert!(solved.routes[0].fitness <= 2.0 * bound);
+        // The bound does not hold on matrices that are not metric.
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [[0,1,5],[1,0,1],[5,1,0]],
+                "n_generations": 10,
+                "target_gap_percent": 10
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_route_hash() {
+        // A route, its rotations and its reverse share a hash, other routes
+        // do not.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let hash = |body: &str| {
+            let response = client
+                .post("/tsp/route/hash")
+                .header(http::ContentType::JSON)
+                .body(body)
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let hashed: json::Value =
+                serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            hashed["hash"].as_str().unwrap().to_string()
+        };
+        let route = hash(r##"{"route": [2, 0, 3, 1, 4]}"##);
+        assert_eq!(route, hash(r##"{"route": [4, 1, 3, 0, 2]}"##));
+        assert_eq!(route, hash(r##"{"route": [3, 1, 4, 2, 0]}"##));
+        assert_ne!(route, hash(r##"{"route": [0, 1, 2, 3, 4]}"##));
+        assert_ne!(
+            hash(r##"{"route": [2, 0, 3, 1, 4], "asymmetric": true}"##),
+            hash(r##"{"route": [4, 1, 3, 0, 2], "asymmetric": true}"##)
+        );
+        let response = client
+            .post("/tsp/route/hash")
+            .header(http::ContentType::JSON)
+            .body(r##"{"route": [0, 0, 1]}"##)
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_tsp_report_normalized_distance() {
+        // The best tour is shorter than an average random tour.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "distances": [
+                    [0,64,378,519,434,200],
+                    [64,0,318,455,375,164],
+                    [378,318,0,170,265,344],
+                    [519,455,170,0,223,428],
+                    [434,375,265,223,0,273],
+                    [200,164,344,428,273,0]],
+                "n_generations": 100,
+                "report_normalized_distance": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solved =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).unwrap();
+        let normalized = solved.meta.normalized_distance.unwrap();
+        assert!(normalized > 0.0 && normalized < 1.0);
+    }
+    #[test]
+    fn test_coordinates_report_crossings() {
+        // A 2-opt refined tour through points in a plane does not cross
+        // itself, and crossings are only counted for planar inputs.
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/coordinates/3d")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "points": [[0,0,1],[2,0,1],[4,0,1],[4,2,1],[2,2,1],[0,2,1]],
+                "n_generations": 10,
+                "two_opt": true,
+                "report_crossings": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let solved =
+            serde_json::from_str::<SolveTspResponse>(&response.into_string().unwrap()).unwrap();
+        assert_eq!(solved.meta.crossings, Some(0));
+        let response = client
+            .post("/tsp/coordinates/3d")
+            .header(http::ContentType::JSON)
+            .body(
+                r##"{
+                "points": [[0,0,0],[2,0,1],[4,0,0],[4,2,1]],
+                "n_generations": 10,
+                "report_crossings": true
+                }"##,
+            )
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_matrix_compress() {
+        // Compressing and decompressing recovers every distance up to half
+        // the scale, with a given and with the default scale.
+        let distances = vec![
+            vec![0.0, 64.3, 378.71, 519.0],
+            vec![64.3, 0.0, 318.25, 455.5],
+            vec![378.71, 318.25, 0.0, 170.02],
+            vec![519.0, 455.5, 170.02, 0.0],
+        ];
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        for request in [
+            json::json!({"distances": distances, "scale": 0.1}),
+            json::json!({"distances": distances}),
+        ] {
+            let response = client
+                .post("/tsp/matrix/compress")
+                .header(http::ContentType::JSON)
+                .body(request.to_string())
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let compressed = response.into_string().unwrap();
+            let scale = serde_json::from_str::<CompressedMatrix>(&compressed)
+                .unwrap()
+                .scale;
+            let response = client
+                .post("/tsp/matrix/decompress")
+                .header(http::ContentType::JSON)
+                .body(compressed)
+                .dispatch();
+            assert_eq!(response.status(), http::Status::Ok);
+            let recovered: Vec<Vec<f64>> =
+                serde_json::from_str(&response.into_string().unwrap()).unwrap();
+            for (original, recovered) in distances.iter().flatten().zip(recovered.iter().flatten())
+            {
+                assert!((original - recovered).abs() <= scale / 2.0 + 1e-9);
+            }
+        }
+        let response = client
+            .post("/tsp/matrix/compress")
+            .header(http::ContentType::JSON)
+            .body(json::json!({"distances": distances, "scale": 1e-9}).to_string())
+            .dispatch();
+        assert_eq!(response.status(), http::Status::BadRequest);
+    }
+    #[test]
+    fn test_route_insertion_costs() {
+        // The cheapest city comes first, with the cost of inserting it
+        // between the best pair of neighbours.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = [0, 2, 4];
+        let client = blocking::Client::tracked(rocket()).unwrap();
+        let response = client
+            .post("/tsp/route/insertion-costs")
+            .header(http::ContentType::JSON)
+            .body(json::json!({"distances": distances, "route": route}).to_string())
+            .dispatch();
+        assert_eq!(response.status(), http::Status::Ok);
+        let result: json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let costs: Vec<InsertionCost> = serde_json::from_value(result["costs"].clone()).unwrap();
+        assert_eq!(costs.len(), 3);
+        assert!(costs
+            .windows(2)
+            .all(|pair| pair[0].added_distance <= pair[1].added_distance));
+        let direct = |city: usize| {
+            (0..route.len())
+                .map(|leg| {
+                    let (from, to) = (route[leg], route[(leg + 1) % route.len()]);
+                    distances[from][city] + distances[city][to] - distances[from][to]
+                })
+                .fold(f64::INFINITY, f64::min)
+        };
+        let cheapest = [1, 3, 5]
+            .into_iter()
+            .min_by(|&a, &b| direct(a).partial_cmp(&direct(b)).unwrap())
+            .unwrap();
+        assert_eq!(costs[0].city, cheapest);
+        assert_eq!(costs[0].added_distance, direct(cheapest));
     }
 }
@@ -1,5 +1,10 @@
 use genetic_algorithm_traits::Population;
 use genetic_algorithm_tsp::{distance_mat, route};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::time;
 
 /// From a `std::time::Duration` object compute the elapsed microseconds.
@@ -24,38 +29,3813 @@ pub fn duration_to_ms(duration: time::Duration) -> u64 {
     (1000 * 1000 * 1000 * duration.as_secs() + nano_seconds) / (1000 * 1000)
 }
 
+/// Most generations `solve_tsp_core` can run, as the mutation probability
+/// decays in this many steps.
+pub const MAX_GENERATIONS: usize = 10000;
+
+/// Check that `solve_tsp_core` can run with `config`: it needs between 1
+/// and `MAX_GENERATIONS` generations, a population and at least one route
+/// to return.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let config = tsp_solver::SolverConfig {
+///     n_generations: 10,
+///     ..Default::default()
+/// };
+/// assert!(tsp_solver::validate_config(&config).is_ok());
+/// assert!(tsp_solver::validate_config(&tsp_solver::SolverConfig { top_n: 0, ..config }).is_err());
+/// ```
+pub fn validate_config(config: &SolverConfig) -> Result<(), String> {
+    if !(1..=MAX_GENERATIONS).contains(&config.n_generations) {
+        return Err(format!(
+            "n_generations has to be in [1, {}].",
+            MAX_GENERATIONS
+        ));
+    }
+    if config.n_routes == 0 {
+        return Err(String::from("n_routes has to be at least 1."));
+    }
+    if config.top_n == 0 {
+        return Err(String::from("top_n has to be at least 1."));
+    }
+    if config.check_interval == 0 {
+        return Err(String::from("check_interval has to be at least 1."));
+    }
+    Ok(())
+}
+
+/// Configuration of the genetic algorithm run by `solve_tsp_core`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverConfig {
+    /// How many generations should the algorithm run for?
+    pub n_generations: usize,
+    /// How many routes should be kept in the population.
+    pub n_routes: usize,
+    /// How many random routes should be ingested in every generation.
+    pub n_random_individuals_per_generation: usize,
+    /// How many of the fittest routes are returned.
+    pub top_n: usize,
+    /// Probability in `[0, 1]` that an offspring is created by crossover of
+    /// two parents rather than by cloning one of them.
+    pub crossover_rate: f32,
+    /// Seed of the random number generator. Runs with the same seed and
+    /// configuration return the same routes.
+    pub seed: u64,
+    /// How the initial population is built.
+    pub init: InitStrategy,
+    /// How the routes that are added in every generation are chosen.
+    pub injection: Injection,
+    /// How routes of equal distance are ranked.
+    pub tie_break: TieBreak,
+    /// Whether to record the lineage of the best route, which keeps every
+    /// route that was created during the run in memory.
+    pub track_lineage: bool,
+    /// How offspring are mutated.
+    pub mutation_op: MutationOp,
+    /// What the genetic algorithm minimizes.
+    pub objective: Objective,
+    /// Whether to count the distinct tours that are evaluated during the
+    /// run, see `SolveOutcome::unique_tours_explored`.
+    pub track_exploration: bool,
+    /// Stop early after this many generations without an improvement of
+    /// the best route by more than `min_improvement`.
+    pub patience: Option<usize>,
+    /// The smallest decrease of the best objective value, counted from
+    /// the last improvement, that resets the patience. Smaller improvements
+    /// add up until they exceed it.
+    pub min_improvement: f64,
+    /// Stop early as soon as the best objective value is at most this.
+    pub target_objective: Option<f64>,
+    /// Record the diversity and check the stop conditions only every this
+    /// many generations and after the last one, which saves bookkeeping on
+    /// small matrices. A run stops at most `check_interval - 1` generations
+    /// after a stop condition is met.
+    pub check_interval: usize,
+}
+impl Default for SolverConfig {
+    /// The default configuration uses a fresh random seed.
+    fn default() -> Self {
+        SolverConfig {
+            n_generations: 1000,
+            n_routes: 30,
+            n_random_individuals_per_generation: 10,
+            top_n: 3,
+            crossover_rate: 1.0,
+            seed: random_seed(),
+            init: InitStrategy::default(),
+            injection: Injection::default(),
+            tie_break: TieBreak::default(),
+            track_lineage: false,
+            mutation_op: MutationOp::default(),
+            objective: Objective::default(),
+            track_exploration: false,
+            patience: None,
+            min_improvement: 0.0,
+            target_objective: None,
+            check_interval: 1,
+        }
+    }
+}
+
+/// The random number generator of the solver. It is pinned to ChaCha8,
+/// whose output is specified independently of the platform, so that a seed
+/// gives the same routes on every machine.
+pub type SolverRng = rand_chacha::ChaCha8Rng;
+/// The name of the algorithm of `SolverRng`.
+pub const RNG_ALGORITHM: &str = "chacha8";
+
+/// Draw a new seed for the random number generator. Seeds are kept below
+/// 2^53 so that they survive a round-trip through JSON numbers in clients
+/// that parse them as doubles.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert!(tsp_solver::random_seed() < 1 << 53);
+/// ```
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen_range(0..1 << 53)
+}
+
+/// Create up to `n_routes` distinct random routes through `n_cities`
+/// cities. Fewer routes are returned if there are not enough distinct
+/// routes.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// assert_eq!(tsp_solver::random_routes(4, 3, &mut rng).len(), 4);
+/// // There are only two routes through two cities.
+/// assert_eq!(tsp_solver::random_routes(4, 2, &mut rng).len(), 2);
+/// ```
+pub fn random_routes<R: Rng>(n_routes: usize, n_cities: usize, rng: &mut R) -> Vec<route::Route> {
+    let mut routes = Vec::with_capacity(n_routes);
+    let mut seen = HashSet::with_capacity(n_routes);
+    // Bound the attempts, as there might be less than `n_routes` permutations.
+    for _ in 0..(10 * n_routes + 100) {
+        if routes.len() == n_routes {
+            break;
+        }
+        let mut indexes = (0..n_cities).collect::<Vec<usize>>();
+        indexes.shuffle(rng);
+        if seen.insert(indexes.clone()) {
+            routes.push(route::Route::new(indexes));
+        }
+    }
+    routes
+}
+
+/// Ordered crossover of two parents: a random slice of `parent_a` is kept
+/// in place, the remaining positions are filled with the missing cities in
+/// the order they appear in `parent_b`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp::route::Route;
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let child = tsp_solver::crossover(
+///     &Route::new(vec![0, 1, 2, 3]),
+///     &Route::new(vec![3, 2, 1, 0]),
+///     &mut rng,
+/// );
+/// assert_eq!(child.get_n_nodes(), 4);
+/// ```
+pub fn crossover<R: Rng>(
+    parent_a: &route::Route,
+    parent_b: &route::Route,
+    rng: &mut R,
+) -> route::Route {
+    let n_cities = parent_a.indexes.len();
+    let start = rng.gen_range(0..n_cities);
+    let end = rng.gen_range(start..n_cities) + 1;
+    let kept = &parent_a.indexes[start..end];
+    let mut rest = parent_b
+        .indexes
+        .iter()
+        .filter(|city| !kept.contains(city))
+        .cloned();
+    route::Route::new(
+        (0..n_cities)
+            .map(|position| {
+                if (start..end).contains(&position) {
+                    parent_a.indexes[position]
+                } else {
+                    rest.next().unwrap()
+                }
+            })
+            .collect(),
+    )
+}
+
+/// With probability `mutation_probability` move a random city of the route
+/// to another random position. This is `mutate_with` and
+/// `MutationOp::Insert`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp::route::Route;
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let route = Route::new(vec![0, 1, 2, 3]);
+/// assert_ne!(tsp_solver::mutate(route.clone(), 1.0, &mut rng), route);
+/// ```
+pub fn mutate<R: Rng>(route: route::Route, mutation_probability: f32, rng: &mut R) -> route::Route {
+    mutate_with(route, mutation_probability, MutationOp::Insert, rng)
+}
+
+/// How a route is changed by a mutation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationOp {
+    /// Swap the cities at two random positions.
+    Swap,
+    /// Move a random city to another random position.
+    #[default]
+    Insert,
+    /// Reverse the order of the cities between two random positions.
+    Invert,
+}
+
+/// With probability `mutation_probability` apply the mutation `op` to the
+/// route. A mutated route always differs from the original one.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp::route::Route;
+/// use genetic_algorithm_tsp_api::tsp_solver::{self, MutationOp};
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let route = Route::new(vec![0, 1, 2, 3]);
+/// let mutated = tsp_solver::mutate_with(route.clone(), 1.0, MutationOp::Invert, &mut rng);
+/// assert_ne!(mutated, route);
+/// ```
+pub fn mutate_with<R: Rng>(
+    mut route: route::Route,
+    mutation_probability: f32,
+    op: MutationOp,
+    rng: &mut R,
+) -> route::Route {
+    let n_cities = route.indexes.len();
+    if n_cities < 2 || rng.gen::<f32>() >= mutation_probability {
+        return route;
+    }
+    let from = rng.gen_range(0..n_cities);
+    // Never pick the same position twice.
+    let to = (from + rng.gen_range(1..n_cities)) % n_cities;
+    match op {
+        MutationOp::Swap => route.indexes.swap(from, to),
+        MutationOp::Insert => {
+            let city = route.indexes.remove(from);
+            route.indexes.insert(to, city);
+        }
+        MutationOp::Invert => route.indexes[from.min(to)..=from.max(to)].reverse(),
+    }
+    route
+}
+
+/// Evolve a population by one generation. Every route is paired with every
+/// other route; with probability `crossover_rate` the offspring is their
+/// crossover, otherwise a clone of the first parent. Every offspring is then
+/// mutated. The parents are kept in the population.
+///
+/// # Arguments
+///
+/// * `population` - The routes to evolve.
+/// * `mutation_probability` - The probability to mutate an offspring.
+/// * `crossover_rate` - The probability to create an offspring by crossover.
+/// * `rng` - The source of randomness.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let population = tsp_solver::random_routes(4, 5, &mut rng);
+/// let evolved = tsp_solver::evolve(&population, 0.5, 0.0, &mut rng);
+/// assert_eq!(evolved.len(), 4 * 3 + 4);
+/// ```
+pub fn evolve<R: Rng>(
+    population: &[route::Route],
+    mutation_probability: f32,
+    crossover_rate: f32,
+    rng: &mut R,
+) -> Vec<route::Route> {
+    evolve_with_provenance(
+        population,
+        mutation_probability,
+        crossover_rate,
+        MutationOp::default(),
+        rng,
+    )
+    .into_iter()
+    .map(|(route, _)| route)
+    .collect()
+}
+
+/// The operator that created a route of the genetic algorithm.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    /// Part of the initial population.
+    Initial,
+    /// Injected as a new random route.
+    Random,
+    /// Crossover of two parents.
+    Crossover,
+    /// Mutation of a clone of a parent.
+    Mutation,
+    /// Crossover of two parents, followed by a mutation.
+    CrossoverMutation,
+}
+
+/// Where an offspring of `evolve_with_provenance` comes from: the index of
+/// its first parent in the population and the operator that changed it, if
+/// any.
+type Provenance = (usize, Option<Operator>);
+
+/// Run `evolve` with the mutation `mutation_op` and report the provenance
+/// of every offspring.
+fn evolve_with_provenance<R: Rng>(
+    population: &[route::Route],
+    mutation_probability: f32,
+    crossover_rate: f32,
+    mutation_op: MutationOp,
+    rng: &mut R,
+) -> Vec<(route::Route, Provenance)> {
+    let mut offspring = Vec::with_capacity(population.len() * population.len());
+    for (main_idx, main_parent) in population.iter().enumerate() {
+        for (other_idx, other_parent) in population.iter().enumerate() {
+            // Don't crossover a route with itself.
+            if main_idx == other_idx {
+                continue;
+            }
+            let crossed_over = rng.gen::<f32>() < crossover_rate;
+            let child = if crossed_over {
+                crossover(main_parent, other_parent, rng)
+            } else {
+                main_parent.clone()
+            };
+            let before_mutation = child.indexes.clone();
+            let child = mutate_with(child, mutation_probability, mutation_op, rng);
+            let operator = match (crossed_over, child.indexes != before_mutation) {
+                (true, true) => Some(Operator::CrossoverMutation),
+                (true, false) => Some(Operator::Crossover),
+                (false, true) => Some(Operator::Mutation),
+                (false, false) => None,
+            };
+            offspring.push((child, (main_idx, operator)));
+        }
+    }
+    offspring.extend(
+        population
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(idx, parent)| (parent, (idx, None))),
+    );
+    offspring
+}
+
+/// A step in the lineage of a route: a route that was created in
+/// `generation` by `operator`, generation 0 being the initial population.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LineageStep {
+    /// The generation the route was created in.
+    pub generation: usize,
+    /// The operator that created the route.
+    pub operator: Operator,
+    /// The distance of the route.
+    pub distance: f64,
+}
+
+/// Records all routes that are created during a run of `solve_tsp_core`
+/// together with their first parent.
+struct LineageTracker {
+    /// Every created route as its step and the index of its first parent.
+    steps: Vec<(LineageStep, Option<usize>)>,
+    /// The index in `steps` of every route of the current population.
+    population: Vec<usize>,
+}
+impl LineageTracker {
+    /// Record a new route and return its index in `steps`.
+    fn record(&mut self, step: LineageStep, parent: Option<usize>) -> usize {
+        self.steps.push((step, parent));
+        self.steps.len() - 1
+    }
+
+    /// Return the steps that led to the route at `node`, oldest first.
+    fn lineage(&self, mut node: usize) -> Vec<LineageStep> {
+        let mut lineage = vec![self.steps[node].0.clone()];
+        while let Some(parent) = self.steps[node].1 {
+            lineage.push(self.steps[parent].0.clone());
+            node = parent;
+        }
+        lineage.reverse();
+        lineage
+    }
+}
+
+/// How the initial population of the genetic algorithm is built.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InitStrategy {
+    /// Only random routes.
+    #[default]
+    Random,
+    /// The nearest-neighbor routes from every start city, filled up with
+    /// random routes.
+    NearestNeighbor,
+    /// The greedy-edge route, filled up with random routes.
+    GreedyEdge,
+}
+
+/// How the routes that are added to the population in every generation are
+/// chosen.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Injection {
+    /// Purely random routes.
+    #[default]
+    Random,
+    /// The random routes that share the fewest legs with the population,
+    /// out of several times as many candidates.
+    Diverse,
+}
+
+/// Create up to `n_routes` random routes that are as different from
+/// `population` as possible. Four times as many random candidates are drawn
+/// and the ones that share the fewest undirected legs with the routes of the
+/// population are kept.
+///
+/// # Arguments
+///
+/// * `population` - The routes the new ones should differ from.
+/// * `n_routes` - How many routes to create.
+/// * `n_cities` - The number of cities of every route.
+/// * `rng` - The random number generator to use.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp::route::Route;
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+/// let population = vec![Route::new(vec![0, 1, 2, 3, 4, 5])];
+/// let routes = tsp_solver::diverse_routes(&population, 2, 6, &mut rng);
+/// assert_eq!(routes.len(), 2);
+/// ```
+pub fn diverse_routes<R: Rng>(
+    population: &[route::Route],
+    n_routes: usize,
+    n_cities: usize,
+    rng: &mut R,
+) -> Vec<route::Route> {
+    let leg = |(&a, &b): (&usize, &usize)| (a.min(b), a.max(b));
+    let population_legs = population
+        .iter()
+        .flat_map(|route| {
+            route
+                .indexes
+                .iter()
+                .zip(route.indexes.iter().cycle().skip(1))
+                .map(leg)
+        })
+        .collect::<HashSet<(usize, usize)>>();
+    let mut candidates = random_routes(4 * n_routes, n_cities, rng);
+    candidates.sort_by_cached_key(|route| {
+        route
+            .indexes
+            .iter()
+            .zip(route.indexes.iter().cycle().skip(1))
+            .filter(|&pair| population_legs.contains(&leg(pair)))
+            .count()
+    });
+    candidates.truncate(n_routes);
+    candidates
+}
+
+/// Build a route by always travelling to the closest city that was not
+/// visited yet, starting at `start`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 2.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![2.0, 5.0, 1.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::nearest_neighbor_route(&distances, 0), vec![0, 1, 2, 3]);
+/// ```
+pub fn nearest_neighbor_route(distances: &[Vec<f64>], start: usize) -> Vec<usize> {
+    let n_cities = distances.len();
+    let mut visited = vec![false; n_cities];
+    let mut route = Vec::with_capacity(n_cities);
+    let mut current = start;
+    visited[current] = true;
+    route.push(current);
+    while route.len() < n_cities {
+        current = (0..n_cities)
+            .filter(|&city| !visited[city])
+            .min_by(|&a, &b| {
+                distances[current][a]
+                    .partial_cmp(&distances[current][b])
+                    .unwrap_or(cmp::Ordering::Equal)
+            })
+            .unwrap();
+        visited[current] = true;
+        route.push(current);
+    }
+    route
+}
+
+/// Build a route with the greedy-edge heuristic: go through all edges from
+/// shortest to longest and keep an edge if both cities still have a free
+/// slot and it does not close a cycle too early.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 2.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![2.0, 5.0, 1.0, 0.0],
+/// ];
+/// let route = tsp_solver::greedy_edge_route(&distances);
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 5.0);
+/// ```
+pub fn greedy_edge_route(distances: &[Vec<f64>]) -> Vec<usize> {
+    let n_cities = distances.len();
+    if n_cities < 3 {
+        return (0..n_cities).collect();
+    }
+    let mut edges = (0..n_cities)
+        .flat_map(|from| (0..n_cities).map(move |to| (from, to)))
+        .filter(|(from, to)| from != to)
+        .collect::<Vec<(usize, usize)>>();
+    edges.sort_by(|&(a, b), &(c, d)| {
+        distances[a][b]
+            .partial_cmp(&distances[c][d])
+            .unwrap_or(cmp::Ordering::Equal)
+    });
+    let mut successor: Vec<Option<usize>> = vec![None; n_cities];
+    let mut has_predecessor = vec![false; n_cities];
+    // Every city points to the representative of the path it is part of.
+    let mut path_of = (0..n_cities).collect::<Vec<usize>>();
+    fn find(path_of: &mut [usize], city: usize) -> usize {
+        let mut root = city;
+        while path_of[root] != root {
+            root = path_of[root];
+        }
+        path_of[city] = root;
+        root
+    }
+    let mut n_edges = 0;
+    for (from, to) in edges {
+        if n_edges == n_cities - 1 {
+            break;
+        }
+        if successor[from].is_some() || has_predecessor[to] {
+            continue;
+        }
+        let (path_from, path_to) = (find(&mut path_of, from), find(&mut path_of, to));
+        if path_from == path_to {
+            continue;
+        }
+        successor[from] = Some(to);
+        has_predecessor[to] = true;
+        path_of[path_from] = path_to;
+        n_edges += 1;
+    }
+    // The kept edges form a single path through all cities.
+    let mut current = (0..n_cities).find(|&city| !has_predecessor[city]).unwrap();
+    let mut route = vec![current];
+    while let Some(next) = successor[current] {
+        route.push(next);
+        current = next;
+    }
+    route
+}
+
+/// Build the initial population of `n_routes` distinct routes with the
+/// given strategy.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 2.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![2.0, 5.0, 1.0, 0.0],
+/// ];
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let population = tsp_solver::initial_population(
+///     &distances,
+///     5,
+///     tsp_solver::InitStrategy::GreedyEdge,
+///     &mut rng,
+/// );
+/// assert_eq!(population.len(), 5);
+/// ```
+pub fn initial_population<R: Rng>(
+    distances: &[Vec<f64>],
+    n_routes: usize,
+    init: InitStrategy,
+    rng: &mut R,
+) -> Vec<route::Route> {
+    let n_cities = distances.len();
+    let seeded = match init {
+        InitStrategy::Random => vec![],
+        InitStrategy::NearestNeighbor => (0..n_cities)
+            .map(|start| nearest_neighbor_route(distances, start))
+            .collect(),
+        InitStrategy::GreedyEdge => vec![greedy_edge_route(distances)],
+    };
+    let mut seen = HashSet::with_capacity(n_routes);
+    let mut population = seeded
+        .into_iter()
+        .filter(|indexes| seen.insert(indexes.clone()))
+        .take(n_routes)
+        .map(route::Route::new)
+        .collect::<Vec<route::Route>>();
+    // Fill up with random routes, skipping the ones already present.
+    let missing = n_routes - population.len();
+    population.extend(
+        random_routes(n_routes, n_cities, rng)
+            .into_iter()
+            .filter(|route| !seen.contains(&route.indexes))
+            .take(missing),
+    );
+    population
+}
+
+/// How routes of equal distance are ranked against each other.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreak {
+    /// Routes of equal distance keep their relative order.
+    #[default]
+    None,
+    /// The route with the shorter longest leg, i.e. the more balanced
+    /// route, is ranked first.
+    MaxLeg,
+}
+
+/// What the genetic algorithm minimizes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// The total distance of the route.
+    #[default]
+    Distance,
+    /// The longest leg of the route, see `bottleneck_distance`. Routes with
+    /// the same longest leg are ranked by their total distance.
+    Bottleneck,
+}
+
+/// The longest leg of a closed tour, which the bottleneck-TSP minimizes.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 3.0],
+///     vec![1.0, 0.0, 2.0],
+///     vec![3.0, 2.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::bottleneck_distance(&distances, &[0, 1, 2]), 3.0);
+/// ```
+pub fn bottleneck_distance(distances: &[Vec<f64>], route: &[usize]) -> f64 {
+    route_legs(distances, route)
+        .into_iter()
+        .map(|(_, _, distance)| distance)
+        .fold(0.0, f64::max)
+}
+
+/// The value of `objective` for a closed tour.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver::{self, Objective};
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 3.0],
+///     vec![1.0, 0.0, 2.0],
+///     vec![3.0, 2.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::objective_value(&distances, &[0, 1, 2], Objective::Distance), 6.0);
+/// assert_eq!(tsp_solver::objective_value(&distances, &[0, 1, 2], Objective::Bottleneck), 3.0);
+/// ```
+pub fn objective_value(distances: &[Vec<f64>], route: &[usize], objective: Objective) -> f64 {
+    match objective {
+        Objective::Distance => route_distance(distances, route),
+        Objective::Bottleneck => bottleneck_distance(distances, route),
+    }
+}
+
+/// Sort `routes` from best to worst by `objective`, ranking routes of equal
+/// value by `tie_break`. The sort is stable.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp::route::Route;
+/// use genetic_algorithm_tsp_api::tsp_solver::{self, Objective, TieBreak};
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 3.0, 5.0],
+///     vec![1.0, 0.0, 1.0, 3.0],
+///     vec![3.0, 1.0, 0.0, 1.0],
+///     vec![5.0, 3.0, 1.0, 0.0],
+/// ];
+/// let mut routes = vec![Route::new(vec![0, 1, 2, 3]), Route::new(vec![0, 1, 3, 2])];
+/// tsp_solver::sort_routes(&mut routes, &distances, Objective::Distance, TieBreak::MaxLeg);
+/// assert_eq!(routes[0].indexes, vec![0, 1, 3, 2]);
+/// ```
+pub fn sort_routes(
+    routes: &mut [route::Route],
+    distances: &[Vec<f64>],
+    objective: Objective,
+    tie_break: TieBreak,
+) {
+    let key = |route: &route::Route| {
+        let distance = route_distance(distances, &route.indexes);
+        let primary = match objective {
+            Objective::Distance => distance,
+            Objective::Bottleneck => bottleneck_distance(distances, &route.indexes),
+        };
+        let secondary = match tie_break {
+            TieBreak::None => 0.0,
+            TieBreak::MaxLeg => bottleneck_distance(distances, &route.indexes),
+        };
+        (primary, distance, secondary)
+    };
+    let mut keyed = routes
+        .iter()
+        .map(|route| (key(route), route.clone()))
+        .collect::<Vec<((f64, f64, f64), route::Route)>>();
+    keyed.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+    for (slot, (_, route)) in routes.iter_mut().zip(keyed) {
+        *slot = route;
+    }
+}
+
+/// Keep the `n` best distinct routes, sorted from best to worst with
+/// `sort_routes`.
+fn fittest(
+    routes: Vec<route::Route>,
+    n: usize,
+    distances: &[Vec<f64>],
+    objective: Objective,
+    tie_break: TieBreak,
+) -> Vec<route::Route> {
+    let mut seen = HashSet::with_capacity(routes.len());
+    let mut distinct = routes
+        .into_iter()
+        .filter(|route| seen.insert(route.indexes.clone()))
+        .collect::<Vec<route::Route>>();
+    sort_routes(&mut distinct, distances, objective, tie_break);
+    distinct.truncate(n);
+    distinct
+}
+
+/// Measure how diverse a population is as the number of distinct undirected
+/// legs used by its routes relative to the number of legs of all routes.
+/// The value is `1 / n_routes` if all routes are the same tour and `1` if
+/// no two routes share a leg.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp::route::Route;
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let same_tour = vec![Route::new(vec![0, 1, 2, 3]), Route::new(vec![1, 2, 3, 0])];
+/// assert_eq!(tsp_solver::population_diversity(&same_tour), 0.5);
+/// ```
+pub fn population_diversity(population: &[route::Route]) -> f64 {
+    let n_legs = population
+        .iter()
+        .map(|route| route.indexes.len())
+        .sum::<usize>();
+    if n_legs == 0 {
+        return 0.0;
+    }
+    let distinct_legs = population
+        .iter()
+        .flat_map(|route| {
+            route
+                .indexes
+                .iter()
+                .zip(route.indexes.iter().cycle().skip(1))
+                .map(|(&a, &b)| (a.min(b), a.max(b)))
+        })
+        .collect::<HashSet<(usize, usize)>>();
+    distinct_legs.len() as f64 / n_legs as f64
+}
+
+/// Result of a run of `solve_tsp_core`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveOutcome {
+    /// The `top_n` fittest routes, from best to worst.
+    pub routes: Vec<route::Route>,
+    /// How many generations were evolved.
+    pub generations_run: usize,
+    /// The average value of `config.objective` over the population after
+    /// every generation, i.e. the average distance by default.
+    pub avg_history: Vec<f64>,
+    /// The value of `config.objective` of the best route after every
+    /// generation.
+    pub best_history: Vec<f64>,
+    /// The diversity of the population after every check, see
+    /// `population_diversity` and `SolverConfig::check_interval`.
+    pub diversity_history: Vec<f64>,
+    /// The mutation probability used in every generation.
+    pub mutation_schedule: Vec<f32>,
+    /// The final population, from the best to the worst route.
+    pub population: Vec<route::Route>,
+    /// The lineage of the best route if `config.track_lineage` is set.
+    pub lineage: Option<Vec<LineageStep>>,
+    /// The number of distinct tours that were evaluated if
+    /// `config.track_exploration` is set. Rotations of a tour, and on
+    /// symmetric matrices its reversal, count as the same tour. At most
+    /// `MAX_TRACKED_TOURS` tours are counted.
+    pub unique_tours_explored: Option<usize>,
+    /// Why the run stopped.
+    pub stop_reason: StopReason,
+    /// How often the stop conditions were checked.
+    pub checks_run: usize,
+}
+
+/// Why a run of `solve_tsp_core` stopped.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// All `config.n_generations` generations were run.
+    Generations,
+    /// The best route did not improve for `config.patience` generations.
+    Patience,
+    /// The best route reached `config.target_objective`, which is derived
+    /// from a target gap to a lower bound.
+    TargetGap,
+}
+
+/// Most distinct tours `solve_tsp_core` keeps in memory to count the
+/// explored tours.
+pub const MAX_TRACKED_TOURS: usize = 1_000_000;
+
+/// The set of distinct tours evaluated during a run, in canonical form.
+struct ExplorationTracker {
+    /// The canonical form of every evaluated tour.
+    tours: HashSet<Vec<usize>>,
+    /// Whether a tour and its reversal are the same tour.
+    reversible: bool,
+}
+impl ExplorationTracker {
+    /// Record the evaluated `routes`, unless `MAX_TRACKED_TOURS` are known.
+    fn record(&mut self, routes: &[route::Route]) {
+        for route in routes {
+            if self.tours.len() == MAX_TRACKED_TOURS {
+                return;
+            }
+            self.tours
+                .insert(canonical_route(&route.indexes, self.reversible));
+        }
+    }
+}
+
+/// Run the genetic algorithm on the traveling-salesman-problem defined by
+/// the distance matrix and return the `config.top_n` fittest routes
+/// together with statistics of the run.
+///
+/// # Arguments
+///
+/// * `distances` - These distances define the fitness of an invidual.
+/// * `config` - The configuration of the genetic algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0, 3.0],
+///     vec![1.0, 0.0, 4.0, 5.0],
+///     vec![2.0, 4.0, 0.0, 6.0],
+///     vec![3.0, 5.0, 6.0, 0.0],
+/// ];
+/// let config = tsp_solver::SolverConfig {
+///     n_generations: 10,
+///     n_routes: 5,
+///     top_n: 1,
+///     ..tsp_solver::SolverConfig::default()
+/// };
+/// let outcome = tsp_solver::solve_tsp_core(&distances, &config);
+/// assert_eq!(outcome.routes.len(), 1);
+/// assert_eq!(outcome.avg_history.len(), outcome.generations_run);
+/// ```
+pub fn solve_tsp_core(distances: &[Vec<f64>], config: &SolverConfig) -> SolveOutcome {
+    let mut rng = SolverRng::seed_from_u64(config.seed);
+    let population = initial_population(distances, config.n_routes, config.init, &mut rng);
+    evolve_population(distances, config, population, &mut rng)
+}
+
+/// Run the genetic algorithm like `solve_tsp_core`, but start from the
+/// given `population` instead of building one as configured by
+/// `config.init`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0, 3.0],
+///     vec![1.0, 0.0, 4.0, 5.0],
+///     vec![2.0, 4.0, 0.0, 6.0],
+///     vec![3.0, 5.0, 6.0, 0.0],
+/// ];
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let population = tsp_solver::random_routes(5, 4, &mut rng);
+/// let config = tsp_solver::SolverConfig {
+///     n_generations: 10,
+///     n_routes: 5,
+///     top_n: 1,
+///     ..tsp_solver::SolverConfig::default()
+/// };
+/// let outcome = tsp_solver::solve_tsp_from_population(&distances, &config, population);
+/// assert_eq!(outcome.routes.len(), 1);
+/// ```
+pub fn solve_tsp_from_population(
+    distances: &[Vec<f64>],
+    config: &SolverConfig,
+    population: Vec<route::Route>,
+) -> SolveOutcome {
+    let mut rng = SolverRng::seed_from_u64(config.seed);
+    evolve_population(distances, config, population, &mut rng)
+}
+
+/// A random population drawn ahead of a solve, together with the state of
+/// the random number generator after drawing it. Evolving it with
+/// `solve_tsp_from_seeded_population` gives the same result as
+/// `solve_tsp_core` with the random initialization and `seed`.
+#[derive(Clone, Debug)]
+pub struct SeededPopulation {
+    /// The seed the population was drawn with.
+    pub seed: u64,
+    routes: Vec<route::Route>,
+    rng: SolverRng,
+}
+
+impl SeededPopulation {
+    /// Draw `n_routes` random routes through `n_cities` cities with `seed`,
+    /// as the random initialization of `solve_tsp_core` does.
+    pub fn new(seed: u64, n_routes: usize, n_cities: usize) -> SeededPopulation {
+        let mut rng = SolverRng::seed_from_u64(seed);
+        let routes = random_routes(n_routes, n_cities, &mut rng);
+        SeededPopulation { seed, routes, rng }
+    }
+}
+
+/// Run the genetic algorithm like `solve_tsp_core` with the random
+/// initialization, but start from the pre-drawn `population`. The seed of
+/// the population replaces `config.seed`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0, 3.0],
+///     vec![1.0, 0.0, 4.0, 5.0],
+///     vec![2.0, 4.0, 0.0, 6.0],
+///     vec![3.0, 5.0, 6.0, 0.0],
+/// ];
+/// let config = tsp_solver::SolverConfig {
+///     n_generations: 10,
+///     n_routes: 5,
+///     seed: 7,
+///     ..tsp_solver::SolverConfig::default()
+/// };
+/// let population = tsp_solver::SeededPopulation::new(7, 5, 4);
+/// let pooled = tsp_solver::solve_tsp_from_seeded_population(&distances, &config, population);
+/// let direct = tsp_solver::solve_tsp_core(&distances, &config);
+/// assert_eq!(
+///     pooled.routes.iter().map(|route| &route.indexes).collect::<Vec<_>>(),
+///     direct.routes.iter().map(|route| &route.indexes).collect::<Vec<_>>()
+/// );
+/// ```
+pub fn solve_tsp_from_seeded_population(
+    distances: &[Vec<f64>],
+    config: &SolverConfig,
+    population: SeededPopulation,
+) -> SolveOutcome {
+    let SeededPopulation {
+        seed,
+        routes,
+        mut rng,
+    } = population;
+    let config = SolverConfig {
+        seed,
+        ..config.clone()
+    };
+    evolve_population(distances, &config, routes, &mut rng)
+}
+
+/// Evolve `population` for `config.n_generations` generations, the shared
+/// part of `solve_tsp_core` and `solve_tsp_from_population`.
+fn evolve_population<R: Rng>(
+    distances: &[Vec<f64>],
+    config: &SolverConfig,
+    mut population: Vec<route::Route>,
+    rng: &mut R,
+) -> SolveOutcome {
+    let n_cities = distances.len();
+    let mut avg_history = Vec::new();
+    let mut best_history = Vec::new();
+    let mut diversity_history = Vec::new();
+    let mut mutation_schedule = Vec::new();
+    let mut tracker = config.track_lineage.then(|| LineageTracker {
+        steps: Vec::new(),
+        population: Vec::new(),
+    });
+    if let Some(tracker) = tracker.as_mut() {
+        tracker.population = population
+            .iter()
+            .map(|route| {
+                let step = LineageStep {
+                    generation: 0,
+                    operator: Operator::Initial,
+                    distance: route_distance(distances, &route.indexes),
+                };
+                tracker.record(step, None)
+            })
+            .collect();
+    }
+    let mut exploration = config.track_exploration.then(|| ExplorationTracker {
+        tours: HashSet::new(),
+        reversible: is_symmetric(distances, 1e-9),
+    });
+    if let Some(exploration) = exploration.as_mut() {
+        exploration.record(&population);
+    }
+    let mut stop_reason = StopReason::Generations;
+    // The best value at the last improvement that reset the patience,
+    // starting with the initial population.
+    let mut reference_best = population
+        .iter()
+        .map(|route| objective_value(distances, &route.indexes, config.objective))
+        .fold(f64::INFINITY, f64::min);
+    // The generation of the last improvement that reset the patience.
+    let mut improved_in = 0;
+    let mut checks_run = 0;
+    // Decay mutation probability.
+    let schedule = (0..MAX_GENERATIONS as i32).step_by(MAX_GENERATIONS / config.n_generations);
+    let last_generation = schedule.len();
+    for mutation_probability_int in schedule {
+        let generation = mutation_schedule.len() + 1;
+        let mutation_probability = 1.0 - (f64::from(mutation_probability_int) / 10000.0) as f32;
+        mutation_schedule.push(mutation_probability);
+        let evolved = evolve_with_provenance(
+            &population,
+            mutation_probability,
+            config.crossover_rate,
+            config.mutation_op,
+            rng,
+        );
+        // Offspring that are unchanged copies of their parent inherit its
+        // place in the lineage.
+        let mut offspring_nodes = Vec::new();
+        if let Some(tracker) = tracker.as_mut() {
+            for (route, (parent, operator)) in &evolved {
+                let parent_node = tracker.population[*parent];
+                offspring_nodes.push(match operator {
+                    Some(operator) if route.indexes != population[*parent].indexes => {
+                        let step = LineageStep {
+                            generation,
+                            operator: *operator,
+                            distance: route_distance(distances, &route.indexes),
+                        };
+                        tracker.record(step, Some(parent_node))
+                    }
+                    _ => parent_node,
+                });
+            }
+        }
+        let mut offspring = evolved
+            .into_iter()
+            .map(|(route, _)| route)
+            .collect::<Vec<route::Route>>();
+        // Add a few random inidividuals each round.
+        let injected = match config.injection {
+            Injection::Random => {
+                random_routes(config.n_random_individuals_per_generation, n_cities, rng)
+            }
+            Injection::Diverse => diverse_routes(
+                &population,
+                config.n_random_individuals_per_generation,
+                n_cities,
+                rng,
+            ),
+        };
+        if let Some(tracker) = tracker.as_mut() {
+            for route in &injected {
+                let step = LineageStep {
+                    generation,
+                    operator: Operator::Random,
+                    distance: route_distance(distances, &route.indexes),
+                };
+                offspring_nodes.push(tracker.record(step, None));
+            }
+        }
+        offspring.extend(injected);
+        if let Some(exploration) = exploration.as_mut() {
+            exploration.record(&offspring);
+        }
+        // `fittest` keeps the first of equal routes, and so does the lookup.
+        let mut node_of = HashMap::new();
+        for (route, &node) in offspring.iter().zip(offspring_nodes.iter()) {
+            node_of.entry(route.indexes.clone()).or_insert(node);
+        }
+        population = fittest(
+            offspring,
+            config.n_routes,
+            distances,
+            config.objective,
+            config.tie_break,
+        );
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.population = population
+                .iter()
+                .map(|route| node_of[&route.indexes])
+                .collect();
+        }
+        let population_distances = population
+            .iter()
+            .map(|route| objective_value(distances, &route.indexes, config.objective))
+            .collect::<Vec<f64>>();
+        avg_history.push(population_distances.iter().sum::<f64>() / population.len() as f64);
+        best_history.push(population_distances[0]);
+        if generation % config.check_interval.max(1) != 0 && generation != last_generation {
+            continue;
+        }
+        checks_run += 1;
+        diversity_history.push(population_diversity(&population));
+        if reference_best - population_distances[0] > config.min_improvement {
+            reference_best = population_distances[0];
+            improved_in = generation;
+        }
+        if config
+            .patience
+            .is_some_and(|patience| generation - improved_in >= patience)
+        {
+            stop_reason = StopReason::Patience;
+            break;
+        }
+        if config
+            .target_objective
+            .is_some_and(|target| population_distances[0] <= target)
+        {
+            stop_reason = StopReason::TargetGap;
+            break;
+        }
+    }
+    SolveOutcome {
+        routes: population.iter().take(config.top_n).cloned().collect(),
+        generations_run: mutation_schedule.len(),
+        avg_history,
+        best_history,
+        diversity_history,
+        mutation_schedule,
+        lineage: tracker
+            .as_ref()
+            .map(|tracker| tracker.lineage(tracker.population[0])),
+        unique_tours_explored: exploration.map(|exploration| exploration.tours.len()),
+        stop_reason,
+        checks_run,
+        population,
+    }
+}
+
 /// Compute an route that for the traveling-salesman-problem defined by
 /// the distance matrix.
 ///
-/// # Arguments
+/// # Arguments
+///
+/// * `distance_matrix` - These distances define the fitness of an invidual.
+/// * `n_generation` - How many generations should the algorithm run for?
+/// * `n_routes` - How many routes should be kept in the population.
+/// * `n_random_route_per_generation` - How many random routes should be
+///   ingested in every generation to allow?
+pub fn solve_tsp(
+    distance_matrix: &distance_mat::DistanceMat,
+    n_generations: usize,
+    n_routes: usize,
+    n_random_individuals_per_generation: usize,
+    top_n: usize,
+) -> Vec<route::Route> {
+    let initial_population = distance_matrix.get_random_population(n_routes);
+    // Decay mutation probability.
+    (0..10000)
+        .step_by(10000 / n_generations)
+        .fold(
+            initial_population,
+            |population, mutation_probability_int| {
+                population
+                    .evolve(1.0 - (f64::from(mutation_probability_int) / 10000.0) as f32)
+                    // Add a few random inidividuals each round.
+                    .add_n_random_nodes(n_random_individuals_per_generation)
+                    .get_fittest_population(n_routes, distance_matrix)
+            },
+        )
+        .get_n_fittest(top_n, distance_matrix)
+}
+
+/// Extract the sub-matrix induced by `indices` from a distance matrix.
+///
+/// The entry `(a, b)` of the returned matrix is the distance between the
+/// cities `indices[a]` and `indices[b]` of the original matrix.
+///
+/// # Arguments
+///
+/// * `distances` - The full distance matrix.
+/// * `indices` - The cities to keep, in the order they should appear in the
+///   sub-matrix. They must be distinct and in range.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// let sub = tsp_solver::submatrix(&distances, &[2, 0]).unwrap();
+/// assert_eq!(sub, vec![vec![0.0, 2.0], vec![2.0, 0.0]]);
+/// ```
+pub fn submatrix(distances: &[Vec<f64>], indices: &[usize]) -> Result<Vec<Vec<f64>>, String> {
+    let n_cities = distances.len();
+    let mut seen = vec![false; n_cities];
+    for &index in indices {
+        if index >= n_cities {
+            return Err(format!(
+                "Index {} is out of range for a matrix with {} cities.",
+                index, n_cities
+            ));
+        }
+        if seen[index] {
+            return Err(format!("Index {} is given more than once.", index));
+        }
+        seen[index] = true;
+    }
+    Ok(indices
+        .iter()
+        .map(|&from| indices.iter().map(|&to| distances[from][to]).collect())
+        .collect())
+}
+
+/// Get all legs `(from, to, distance)` of a closed tour, including the leg
+/// returning from the last city to the first one.
+///
+/// # Arguments
+///
+/// * `distances` - The distance matrix the route is defined on.
+/// * `route` - The order in which the cities are visited.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// assert_eq!(
+///     tsp_solver::route_legs(&distances, &[0, 1, 2]),
+///     vec![(0, 1, 1.0), (1, 2, 3.0), (2, 0, 2.0)]
+/// );
+/// ```
+pub fn route_legs(distances: &[Vec<f64>], route: &[usize]) -> Vec<(usize, usize, f64)> {
+    route
+        .iter()
+        .zip(route.iter().cycle().skip(1))
+        .map(|(&from, &to)| (from, to, distances[from][to]))
+        .collect()
+}
+
+/// The mean radius of the earth used by `haversine_matrix`.
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Build the euclidean distance matrix of points in 3D space.
+///
+/// # Arguments
+///
+/// * `points` - The `[x, y, z]`-coordinates of the cities.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::euclidean_matrix_3d(&[[0.0, 0.0, 0.0], [1.0, 2.0, 2.0]]);
+/// assert_eq!(distances, vec![vec![0.0, 3.0], vec![3.0, 0.0]]);
+/// ```
+pub fn euclidean_matrix_3d(points: &[[f64; 3]]) -> Vec<Vec<f64>> {
+    points
+        .iter()
+        .map(|from| {
+            points
+                .iter()
+                .map(|to| {
+                    from.iter()
+                        .zip(to.iter())
+                        .map(|(a, b)| (a - b) * (a - b))
+                        .sum::<f64>()
+                        .sqrt()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Build the matrix of great-circle distances in kilometers between points
+/// on earth with the haversine formula.
+///
+/// # Arguments
+///
+/// * `points` - The `[latitude, longitude]`-coordinates of the cities in degrees.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::haversine_matrix(&[[0.0, 0.0], [0.0, 180.0]]).unwrap();
+/// assert!((distances[0][1] - 20015.1).abs() < 0.1);
+/// ```
+pub fn haversine_matrix(points: &[[f64; 2]]) -> Result<Vec<Vec<f64>>, String> {
+    if let Some([latitude, longitude]) = points.iter().find(|[latitude, longitude]| {
+        !(-90.0..=90.0).contains(latitude) || !(-180.0..=180.0).contains(longitude)
+    }) {
+        return Err(format!(
+            "[{}, {}] is not a valid [latitude, longitude].",
+            latitude, longitude
+        ));
+    }
+    Ok(points
+        .iter()
+        .map(|&[from_latitude, from_longitude]| {
+            points
+                .iter()
+                .map(|&[to_latitude, to_longitude]| {
+                    let half_latitude = (to_latitude - from_latitude).to_radians() / 2.0;
+                    let half_longitude = (to_longitude - from_longitude).to_radians() / 2.0;
+                    let a = half_latitude.sin().powi(2)
+                        + from_latitude.to_radians().cos()
+                            * to_latitude.to_radians().cos()
+                            * half_longitude.sin().powi(2);
+                    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Project points on earth to planar `[x, y, 0]`-coordinates in
+/// kilometers with the equirectangular projection around their mean
+/// latitude. Euclidean distances between the projected points approximate
+/// the great-circle distances for points that are close to each other.
+///
+/// # Arguments
+///
+/// * `points` - The `[latitude, longitude]`-coordinates of the cities in degrees.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let points = [[0.0, 0.0], [0.0, 1.0]];
+/// let projected = tsp_solver::euclidean_matrix_3d(&tsp_solver::equirectangular_points(&points));
+/// let great_circle = tsp_solver::haversine_matrix(&points).unwrap();
+/// assert!((projected[0][1] - great_circle[0][1]).abs() < 1e-6);
+/// ```
+pub fn equirectangular_points(points: &[[f64; 2]]) -> Vec<[f64; 3]> {
+    let mean_latitude =
+        points.iter().map(|[latitude, _]| latitude).sum::<f64>() / points.len().max(1) as f64;
+    let scale = mean_latitude.to_radians().cos();
+    points
+        .iter()
+        .map(|&[latitude, longitude]| {
+            [
+                EARTH_RADIUS_KM * longitude.to_radians() * scale,
+                EARTH_RADIUS_KM * latitude.to_radians(),
+                0.0,
+            ]
+        })
+        .collect()
+}
+
+/// Compute the shortest path between all pairs of cities with the
+/// Floyd–Warshall algorithm. Missing edges are expected to be
+/// `f64::INFINITY`; pairs that cannot reach each other stay infinite.
+///
+/// # Arguments
+///
+/// * `distances` - The direct distances between the cities.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, f64::INFINITY],
+///     vec![1.0, 0.0, 2.0],
+///     vec![f64::INFINITY, 2.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::floyd_warshall(&distances)[0][2], 3.0);
+/// ```
+pub fn floyd_warshall(distances: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let mut shortest = distances.to_vec();
+    let n_cities = shortest.len();
+    for via in 0..n_cities {
+        for from in 0..n_cities {
+            for to in 0..n_cities {
+                let through_via = shortest[from][via] + shortest[via][to];
+                if through_via < shortest[from][to] {
+                    shortest[from][to] = through_via;
+                }
+            }
+        }
+    }
+    shortest
+}
+
+/// Compute the number of hops between all pairs of cities of an unweighted,
+/// undirected graph. Pairs that cannot reach each other are `f64::INFINITY`.
+///
+/// # Arguments
+///
+/// * `n_cities` - The number of cities of the graph.
+/// * `edges` - The pairs of cities that are directly connected.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let hops = tsp_solver::hop_distances(3, &[[0, 1], [1, 2]]).unwrap();
+/// assert_eq!(hops[0][2], 2.0);
+/// ```
+pub fn hop_distances(n_cities: usize, edges: &[[usize; 2]]) -> Result<Vec<Vec<f64>>, String> {
+    let mut distances = vec![vec![f64::INFINITY; n_cities]; n_cities];
+    for (city, row) in distances.iter_mut().enumerate() {
+        row[city] = 0.0;
+    }
+    for &[a, b] in edges {
+        if a >= n_cities || b >= n_cities {
+            return Err(format!(
+                "The edge [{}, {}] connects a city that does not exist.",
+                a, b
+            ));
+        }
+        if a != b {
+            distances[a][b] = 1.0;
+            distances[b][a] = 1.0;
+        }
+    }
+    Ok(floyd_warshall(&distances))
+}
+
+/// How missing cells of a distance matrix are filled in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FillStrategy {
+    /// Use the length of the shortest path over the known cells.
+    ShortestPath,
+    /// Use the mean of all known off-diagonal cells.
+    Mean,
+    /// Use the maximum of all known off-diagonal cells.
+    Max,
+}
+
+/// Fill all cells of `distances` that equal `sentinel`. Missing diagonal
+/// cells are always set to zero.
+///
+/// # Arguments
+///
+/// * `distances` - The distance matrix with missing cells.
+/// * `sentinel` - The value that marks a cell as missing.
+/// * `strategy` - How the missing cells are filled in.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, -1.0],
+///     vec![1.0, 0.0, 2.0],
+///     vec![-1.0, 2.0, 0.0],
+/// ];
+/// let completed =
+///     tsp_solver::complete_missing(&distances, -1.0, tsp_solver::FillStrategy::Max).unwrap();
+/// assert_eq!(completed[0][2], 2.0);
+/// // The matrix has to be square.
+/// let jagged = vec![vec![0.0, -1.0], vec![-1.0]];
+/// assert!(tsp_solver::complete_missing(&jagged, -1.0, tsp_solver::FillStrategy::ShortestPath).is_err());
+/// ```
+pub fn complete_missing(
+    distances: &[Vec<f64>],
+    sentinel: f64,
+    strategy: FillStrategy,
+) -> Result<Vec<Vec<f64>>, String> {
+    validate_matrix_shape(distances)?;
+    let known = distances
+        .iter()
+        .enumerate()
+        .flat_map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |&(to, &distance)| to != from && distance != sentinel)
+                .map(|(_, &distance)| distance)
+        })
+        .collect::<Vec<f64>>();
+    let fill_value = match strategy {
+        FillStrategy::ShortestPath => f64::INFINITY,
+        FillStrategy::Mean if !known.is_empty() => known.iter().sum::<f64>() / known.len() as f64,
+        FillStrategy::Max if !known.is_empty() => known.iter().cloned().fold(f64::MIN, f64::max),
+        _ => {
+            return Err(String::from(
+                "The matrix does not contain any known distance.",
+            ))
+        }
+    };
+    let filled = distances
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(to, &distance)| {
+                    if from == to && distance == sentinel {
+                        0.0
+                    } else if distance == sentinel {
+                        fill_value
+                    } else {
+                        distance
+                    }
+                })
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<Vec<f64>>>();
+    if strategy != FillStrategy::ShortestPath {
+        return Ok(filled);
+    }
+    let completed = floyd_warshall(&filled);
+    if completed
+        .iter()
+        .flatten()
+        .any(|distance| distance.is_infinite())
+    {
+        return Err(String::from(
+            "Some cities cannot be reached from each other over the known distances.",
+        ));
+    }
+    Ok(completed)
+}
+
+/// Check that `route` visits each of the `n_cities` cities exactly once.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert!(tsp_solver::validate_route(&[2, 0, 1], 3).is_ok());
+/// assert!(tsp_solver::validate_route(&[2, 2, 1], 3).is_err());
+/// ```
+pub fn validate_route(route: &[usize], n_cities: usize) -> Result<(), String> {
+    if route.len() != n_cities {
+        return Err(format!(
+            "The route visits {} cities, but the matrix has {} cities.",
+            route.len(),
+            n_cities
+        ));
+    }
+    let mut seen = vec![false; n_cities];
+    for &city in route {
+        if city >= n_cities {
+            return Err(format!("City {} does not exist.", city));
+        }
+        if seen[city] {
+            return Err(format!("City {} is visited more than once.", city));
+        }
+        seen[city] = true;
+    }
+    Ok(())
+}
+
+/// A single change made by `repair_route`. All indexes refer to positions in
+/// the route that was repaired.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RouteEdit {
+    /// A duplicate or unknown city was replaced by a missing city.
+    Replace {
+        /// The position of the replaced city.
+        index: usize,
+        /// The city that was replaced.
+        from: usize,
+        /// The missing city it was replaced with.
+        to: usize,
+    },
+    /// A duplicate or unknown city was dropped.
+    Remove {
+        /// The position of the dropped city.
+        index: usize,
+        /// The city that was dropped.
+        city: usize,
+    },
+    /// A missing city was appended to the end of the route.
+    Append {
+        /// The city that was appended.
+        city: usize,
+    },
+}
+
+/// Turn `route` into a valid permutation of the `n_cities` cities with as few
+/// changes as possible while keeping the order of the valid cities. Repeated
+/// and unknown cities are replaced by the missing cities in ascending order,
+/// superfluous ones are removed and remaining missing cities are appended.
+///
+/// # Arguments
+///
+/// * `route` - The route to repair.
+/// * `n_cities` - The number of cities the route has to visit.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver::{self, RouteEdit};
+///
+/// let (repaired, edits) = tsp_solver::repair_route(&[0, 2, 2, 3], 4);
+/// assert_eq!(repaired, vec![0, 2, 1, 3]);
+/// assert_eq!(edits, vec![RouteEdit::Replace { index: 2, from: 2, to: 1 }]);
+/// ```
+pub fn repair_route(route: &[usize], n_cities: usize) -> (Vec<usize>, Vec<RouteEdit>) {
+    let mut present = vec![false; n_cities];
+    for &city in route {
+        if city < n_cities {
+            present[city] = true;
+        }
+    }
+    let mut missing = (0..n_cities).filter(|&city| !present[city]);
+    let mut seen = vec![false; n_cities];
+    let mut repaired = Vec::with_capacity(n_cities);
+    let mut edits = Vec::new();
+    for (index, &city) in route.iter().enumerate() {
+        if city < n_cities && !seen[city] {
+            seen[city] = true;
+            repaired.push(city);
+        } else if let Some(replacement) = missing.next() {
+            repaired.push(replacement);
+            edits.push(RouteEdit::Replace {
+                index,
+                from: city,
+                to: replacement,
+            });
+        } else {
+            edits.push(RouteEdit::Remove { index, city });
+        }
+    }
+    for city in missing {
+        repaired.push(city);
+        edits.push(RouteEdit::Append { city });
+    }
+    (repaired, edits)
+}
+
+/// Compute the distance of the closed tour `route`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::route_distance(&distances, &[0, 1, 2]), 6.0);
+/// ```
+pub fn route_distance(distances: &[Vec<f64>], route: &[usize]) -> f64 {
+    route_legs(distances, route)
+        .iter()
+        .map(|(_, _, distance)| distance)
+        .sum()
+}
+
+/// Change in distance of the closed tour `route` when the segment
+/// `route[i..=j]` is reversed. A negative value is an improvement.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// // Reversing [2, 1] turns the tour 0-2-1-3 into the optimal 0-1-2-3.
+/// assert_eq!(tsp_solver::two_opt_delta(&distances, &[0, 2, 1, 3], 1, 2), -8.0);
+/// ```
+pub fn two_opt_delta(distances: &[Vec<f64>], route: &[usize], i: usize, j: usize) -> f64 {
+    let n_cities = route.len();
+    let before = route[(i + n_cities - 1) % n_cities];
+    let after = route[(j + 1) % n_cities];
+    if before == route[j] || after == route[i] {
+        // The segment covers the whole tour, which only changes its direction.
+        return (i..j)
+            .map(|k| distances[route[k + 1]][route[k]] - distances[route[k]][route[k + 1]])
+            .sum::<f64>()
+            + distances[route[i]][route[j]]
+            - distances[route[j]][route[i]];
+    }
+    let mut delta = distances[before][route[j]] + distances[route[i]][after]
+        - distances[before][route[i]]
+        - distances[route[j]][after];
+    // On asymmetric matrices the legs inside the segment change as well.
+    for k in i..j {
+        delta += distances[route[k + 1]][route[k]] - distances[route[k]][route[k + 1]];
+    }
+    delta
+}
+
+/// Reverse the segment `route[i..=j]` of a route.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::two_opt_move(&[0, 1, 2, 3, 4], 1, 3), vec![0, 3, 2, 1, 4]);
+/// ```
+pub fn two_opt_move(route: &[usize], i: usize, j: usize) -> Vec<usize> {
+    let mut moved = route.to_vec();
+    moved[i..=j].reverse();
+    moved
+}
+
+/// Find the 2-opt move `(i, j, delta)` that shortens the closed tour
+/// `route` the most, or `None` if no move improves it.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// assert!(tsp_solver::best_two_opt_move(&distances, &[0, 2, 1, 3]).is_some());
+/// assert!(tsp_solver::best_two_opt_move(&distances, &[0, 1, 2, 3]).is_none());
+/// ```
+pub fn best_two_opt_move(distances: &[Vec<f64>], route: &[usize]) -> Option<(usize, usize, f64)> {
+    let n_cities = route.len();
+    let mut best_move = None;
+    let mut best_delta = -1e-9;
+    for i in 0..n_cities {
+        for j in (i + 1)..n_cities {
+            let delta = two_opt_delta(distances, route, i, j);
+            if delta < best_delta {
+                best_delta = delta;
+                best_move = Some((i, j, delta));
+            }
+        }
+    }
+    best_move
+}
+
+/// Check whether `distances[a][c] <= distances[a][b] + distances[b][c]`
+/// holds for all cities `a`, `b` and `c`, up to `tolerance`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0],
+///     vec![1.0, 0.0, 1.0],
+///     vec![5.0, 1.0, 0.0],
+/// ];
+/// assert!(!tsp_solver::satisfies_triangle_inequality(&distances, 1e-9));
+/// ```
+pub fn satisfies_triangle_inequality(distances: &[Vec<f64>], tolerance: f64) -> bool {
+    let n_cities = distances.len();
+    (0..n_cities).all(|a| {
+        (0..n_cities).all(|b| {
+            (0..n_cities).all(|c| distances[a][c] <= distances[a][b] + distances[b][c] + tolerance)
+        })
+    })
+}
+
+/// Compute the metric closure of a distance matrix, i.e. replace every
+/// distance by the length of the shortest path between the two cities.
+/// Returns the closure and the number of cells that changed.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0],
+///     vec![1.0, 0.0, 1.0],
+///     vec![5.0, 1.0, 0.0],
+/// ];
+/// let (closure, n_changed) = tsp_solver::metric_closure(&distances);
+/// assert_eq!(closure[0][2], 2.0);
+/// assert_eq!(n_changed, 2);
+/// ```
+pub fn metric_closure(distances: &[Vec<f64>]) -> (Vec<Vec<f64>>, usize) {
+    let closure = floyd_warshall(distances);
+    let n_changed = closure
+        .iter()
+        .flatten()
+        .zip(distances.iter().flatten())
+        .filter(|(shortest, direct)| shortest != direct)
+        .count();
+    (closure, n_changed)
+}
+
+/// Check whether a distance matrix is symmetric up to `tolerance`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert!(tsp_solver::is_symmetric(&[vec![0.0, 1.0], vec![1.0, 0.0]], 1e-9));
+/// assert!(!tsp_solver::is_symmetric(&[vec![0.0, 1.0], vec![2.0, 0.0]], 1e-9));
+/// ```
+pub fn is_symmetric(distances: &[Vec<f64>], tolerance: f64) -> bool {
+    distances.iter().enumerate().all(|(from, row)| {
+        row.iter()
+            .enumerate()
+            .all(|(to, distance)| (distance - distances[to][from]).abs() <= tolerance)
+    })
+}
+
+/// Bring a closed tour into its canonical form: it starts at the smallest
+/// city and, if `reversible` is set, runs in the direction in which the
+/// second city is smaller than the last one. Only set `reversible` for
+/// symmetric matrices, as reversing a tour changes its distance otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::canonical_route(&[2, 3, 0, 1], false), vec![0, 1, 2, 3]);
+/// assert_eq!(tsp_solver::canonical_route(&[2, 1, 0, 3], true), vec![0, 1, 2, 3]);
+/// ```
+pub fn canonical_route(route: &[usize], reversible: bool) -> Vec<usize> {
+    let start = match route.iter().enumerate().min_by_key(|(_, &city)| city) {
+        Some((start, _)) => start,
+        None => return vec![],
+    };
+    let mut canonical = route[start..]
+        .iter()
+        .chain(route[..start].iter())
+        .cloned()
+        .collect::<Vec<usize>>();
+    if reversible && canonical.len() > 2 && canonical[1] > canonical[canonical.len() - 1] {
+        canonical[1..].reverse();
+    }
+    canonical
+}
+
+/// Check that `distances` is a non-empty square matrix, whatever its
+/// entries are, e.g. before missing entries are filled in.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert!(tsp_solver::validate_matrix_shape(&[vec![0.0, -1.0], vec![-1.0, 0.0]]).is_ok());
+/// assert!(tsp_solver::validate_matrix_shape(&[vec![0.0, 1.0], vec![1.0]]).is_err());
+/// assert!(tsp_solver::validate_matrix_shape(&[]).is_err());
+/// ```
+pub fn validate_matrix_shape(distances: &[Vec<f64>]) -> Result<(), String> {
+    if distances.is_empty() {
+        return Err(String::from("The distance matrix is empty."));
+    }
+    match distances
+        .iter()
+        .position(|row| row.len() != distances.len())
+    {
+        Some(from) => Err(format!(
+            "The distance matrix is not square: row {} has {} entries, but there are {} rows.",
+            from,
+            distances[from].len(),
+            distances.len()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Check that `distances` is a non-empty square matrix without negative or
+/// non-finite entries.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert!(tsp_solver::validate_distance_matrix(&[vec![0.0, 1.0], vec![1.0, 0.0]]).is_ok());
+/// assert!(tsp_solver::validate_distance_matrix(&[vec![0.0, 1.0]]).is_err());
+/// ```
+pub fn validate_distance_matrix(distances: &[Vec<f64>]) -> Result<(), String> {
+    validate_matrix_shape(distances)?;
+    for (from, row) in distances.iter().enumerate() {
+        if let Some(to) = row
+            .iter()
+            .position(|distance| !distance.is_finite() || *distance < 0.0)
+        {
+            return Err(format!(
+                "The distance from {} to {} has to be finite and non-negative.",
+                from, to
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that no closed tour through `distances` can be longer than
+/// `max_tour_distance`, so that summing the legs of a tour neither
+/// overflows to infinity nor loses all precision. Every tour leaves every
+/// city exactly once, so the longest leg out of every city is summed up.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 1.0], vec![2.0, 0.0]];
+/// assert!(tsp_solver::validate_tour_magnitude(&distances, 3.0).is_ok());
+/// assert!(tsp_solver::validate_tour_magnitude(&distances, 2.5).is_err());
+/// let huge = vec![vec![0.0, f64::MAX], vec![f64::MAX, 0.0]];
+/// assert!(tsp_solver::validate_tour_magnitude(&huge, f64::MAX).is_err());
+/// ```
+pub fn validate_tour_magnitude(
+    distances: &[Vec<f64>],
+    max_tour_distance: f64,
+) -> Result<(), String> {
+    let mut bound = 0.0;
+    for row in distances {
+        bound += row.iter().cloned().fold(0.0, f64::max);
+        // An overflow makes the bound infinite, which fails this check too.
+        if bound > max_tour_distance {
+            return Err(format!(
+                "A tour through these distances can be longer than {}; normalize the matrix, e.g. with /tsp/matrix/normalize-to.",
+                max_tour_distance
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Compute the weight of a minimum spanning tree of the cities, a lower
+/// bound of the distance of every closed tour. Asymmetric matrices are
+/// symmetrized by using the shorter direction of each pair.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 2.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![2.0, 5.0, 1.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::mst_lower_bound(&distances), 3.0);
+/// ```
+pub fn mst_lower_bound(distances: &[Vec<f64>]) -> f64 {
+    let n_cities = distances.len();
+    if n_cities == 0 {
+        return 0.0;
+    }
+    // Prim's algorithm, growing the tree from city 0.
+    let edge = |a: usize, b: usize| distances[a][b].min(distances[b][a]);
+    let mut in_tree = vec![false; n_cities];
+    let mut closest = (0..n_cities)
+        .map(|city| edge(0, city))
+        .collect::<Vec<f64>>();
+    in_tree[0] = true;
+    let mut weight = 0.0;
+    for _ in 1..n_cities {
+        let next = (0..n_cities)
+            .filter(|&city| !in_tree[city])
+            .min_by(|&a, &b| {
+                closest[a]
+                    .partial_cmp(&closest[b])
+                    .unwrap_or(cmp::Ordering::Equal)
+            })
+            .unwrap();
+        in_tree[next] = true;
+        weight += closest[next];
+        for city in 0..n_cities {
+            if !in_tree[city] {
+                closest[city] = closest[city].min(edge(next, city));
+            }
+        }
+    }
+    weight
+}
+
+/// 64-bit FNV-1a hash of `bytes`, continuing from `hash`. Unlike the hashers
+/// of the standard library it is stable across platforms and releases.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Compute a stable hash of a distance matrix and, optionally, of a
+/// configuration in a canonical textual form. The hash only depends on the
+/// dimension and the values of the matrix, so it can be used as a cache key.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 1.0], vec![1.0, 0.0]];
+/// assert_eq!(
+///     tsp_solver::matrix_hash(&distances, None),
+///     tsp_solver::matrix_hash(&distances.clone(), None)
+/// );
+/// assert_ne!(
+///     tsp_solver::matrix_hash(&distances, None),
+///     tsp_solver::matrix_hash(&distances, Some("{\"n_generations\":10}"))
+/// );
+/// ```
+pub fn matrix_hash(distances: &[Vec<f64>], config: Option<&str>) -> String {
+    let mut hash = fnv1a(
+        0xcbf2_9ce4_8422_2325,
+        &(distances.len() as u64).to_le_bytes(),
+    );
+    for row in distances {
+        hash = fnv1a(hash, &(row.len() as u64).to_le_bytes());
+        for &distance in row {
+            // `0.0` and `-0.0` are the same distance.
+            let distance = if distance == 0.0 { 0.0 } else { distance };
+            hash = fnv1a(hash, &distance.to_bits().to_le_bytes());
+        }
+    }
+    if let Some(config) = config {
+        hash = fnv1a(hash, config.as_bytes());
+    }
+    format!("{:016x}", hash)
+}
+
+/// Compute a stable hash of the canonical form of a closed tour, so that
+/// all rotations of a route and, if `reversible` is set, its reverse have
+/// the same hash.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::route_hash(&[1, 2, 0], true), tsp_solver::route_hash(&[0, 2, 1], true));
+/// assert_ne!(tsp_solver::route_hash(&[1, 2, 0], false), tsp_solver::route_hash(&[0, 2, 1], false));
+/// ```
+pub fn route_hash(route: &[usize], reversible: bool) -> String {
+    let canonical = canonical_route(route, reversible);
+    let hash = canonical.iter().fold(
+        fnv1a(
+            0xcbf2_9ce4_8422_2325,
+            &(canonical.len() as u64).to_le_bytes(),
+        ),
+        |hash, &city| fnv1a(hash, &(city as u64).to_le_bytes()),
+    );
+    format!("{:016x}", hash)
+}
+
+/// Improve a closed tour with 2-opt moves until no move improves it or
+/// `max_passes` passes over all moves have been run. Every pass applies all
+/// improving moves it comes across. Returns the improved route and the
+/// number of passes run.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// let (route, _) = tsp_solver::two_opt(&distances, &[0, 2, 1, 3], None);
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 4.0);
+/// ```
+pub fn two_opt(
+    distances: &[Vec<f64>],
+    route: &[usize],
+    max_passes: Option<usize>,
+) -> (Vec<usize>, usize) {
+    two_opt_restricted(distances, route, max_passes, |_, _| true)
+}
+
+/// Run `two_opt`, but only apply moves whose new legs connect two cities
+/// that are candidates of each other: `b` is in `candidates[a]` or `a` is
+/// in `candidates[b]`. Moves that only reverse the whole tour add no new
+/// leg and are always applied.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// let candidates = vec![vec![1], vec![2], vec![3], vec![0]];
+/// let (route, _) = tsp_solver::candidate_two_opt(&distances, &[0, 2, 1, 3], &candidates, None);
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 4.0);
+/// // Without candidates the route cannot change.
+/// let (route, _) = tsp_solver::candidate_two_opt(&distances, &[0, 2, 1, 3], &[], None);
+/// assert_eq!(route, vec![0, 2, 1, 3]);
+/// ```
+pub fn candidate_two_opt(
+    distances: &[Vec<f64>],
+    route: &[usize],
+    candidates: &[Vec<usize>],
+    max_passes: Option<usize>,
+) -> (Vec<usize>, usize) {
+    let is_candidate = |a: usize, b: usize| candidates.get(a).is_some_and(|list| list.contains(&b));
+    two_opt_restricted(distances, route, max_passes, |a, b| {
+        is_candidate(a, b) || is_candidate(b, a)
+    })
+}
+
+/// Run `two_opt` with the moves whose new legs are all `allowed`.
+fn two_opt_restricted(
+    distances: &[Vec<f64>],
+    route: &[usize],
+    max_passes: Option<usize>,
+    allowed: impl Fn(usize, usize) -> bool,
+) -> (Vec<usize>, usize) {
+    let mut route = route.to_vec();
+    let n_cities = route.len();
+    let mut n_passes = 0;
+    while max_passes.is_none_or(|max_passes| n_passes < max_passes) {
+        n_passes += 1;
+        let mut improved = false;
+        for i in 0..n_cities {
+            for j in (i + 1)..n_cities {
+                let before = route[(i + n_cities - 1) % n_cities];
+                let after = route[(j + 1) % n_cities];
+                let whole_tour = before == route[j] || after == route[i];
+                let allowed_move =
+                    whole_tour || (allowed(before, route[j]) && allowed(route[i], after));
+                if allowed_move && two_opt_delta(distances, &route, i, j) < -1e-9 {
+                    route[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    (route, n_passes)
+}
+
+/// Convert a matrix of travel durations into distances travelled at
+/// constant `speed`, i.e. `distance = duration * speed`. The diagonal is
+/// always zero.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances =
+///     tsp_solver::durations_to_distances(&[vec![0.0, 2.0], vec![3.0, 0.0]], 10.0).unwrap();
+/// assert_eq!(distances, vec![vec![0.0, 20.0], vec![30.0, 0.0]]);
+/// ```
+pub fn durations_to_distances(durations: &[Vec<f64>], speed: f64) -> Result<Vec<Vec<f64>>, String> {
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(String::from("The speed has to be positive."));
+    }
+    validate_distance_matrix(durations)
+        .map_err(|message| message.replace("distance", "duration"))?;
+    Ok(durations
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(to, duration)| if from == to { 0.0 } else { duration * speed })
+                .collect()
+        })
+        .collect())
+}
+
+/// Check whether no 2-opt move shortens the closed tour `route`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// assert!(tsp_solver::is_two_opt_optimal(&distances, &[0, 1, 2, 3]));
+/// assert!(!tsp_solver::is_two_opt_optimal(&distances, &[0, 2, 1, 3]));
+/// ```
+pub fn is_two_opt_optimal(distances: &[Vec<f64>], route: &[usize]) -> bool {
+    best_two_opt_move(distances, route).is_none()
+}
+
+/// A partition of the cities into clusters, each represented by one of its
+/// cities.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Clustering {
+    /// The representative city of every cluster.
+    pub medoids: Vec<usize>,
+    /// The cities of every cluster in ascending order, `clusters[i]`
+    /// contains `medoids[i]`.
+    pub clusters: Vec<Vec<usize>>,
+}
+
+/// Partition the cities into `k` clusters with the k-medoids algorithm, which
+/// only needs the distance matrix. The medoids are initialised
+/// deterministically with the most central city followed by the cities
+/// farthest from the medoids chosen so far. Afterwards cities are assigned to
+/// their nearest medoid and each medoid is moved to the city of its cluster
+/// with the smallest total distance to the others until nothing changes.
+///
+/// # Arguments
+///
+/// * `distances` - The distances between the cities, `distances[medoid][city]`
+///   is used to assign a city to a medoid.
+/// * `k` - The number of clusters, between 1 and the number of cities.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 9.0, 9.0],
+///     vec![1.0, 0.0, 9.0, 9.0],
+///     vec![9.0, 9.0, 0.0, 1.0],
+///     vec![9.0, 9.0, 1.0, 0.0],
+/// ];
+/// let clustering = tsp_solver::k_medoids(&distances, 2).unwrap();
+/// assert_eq!(clustering.clusters, vec![vec![0, 1], vec![2, 3]]);
+/// ```
+pub fn k_medoids(distances: &[Vec<f64>], k: usize) -> Result<Clustering, String> {
+    let n_cities = distances.len();
+    if k == 0 || k > n_cities {
+        return Err(format!(
+            "k has to be between 1 and the number of cities ({}), but is {}.",
+            n_cities, k
+        ));
+    }
+    let total_distance = |from: usize, members: &[usize]| -> f64 {
+        members.iter().map(|&to| distances[from][to]).sum()
+    };
+    let all_cities = (0..n_cities).collect::<Vec<usize>>();
+    let most_central = (0..n_cities)
+        .min_by(|&a, &b| {
+            total_distance(a, &all_cities)
+                .partial_cmp(&total_distance(b, &all_cities))
+                .unwrap_or(cmp::Ordering::Equal)
+        })
+        .unwrap();
+    let mut medoids = vec![most_central];
+    let distance_to_medoids = |city: usize, medoids: &[usize]| -> f64 {
+        medoids
+            .iter()
+            .map(|&medoid| distances[medoid][city])
+            .fold(f64::INFINITY, f64::min)
+    };
+    while medoids.len() < k {
+        let farthest = (0..n_cities)
+            .filter(|city| !medoids.contains(city))
+            .max_by(|&a, &b| {
+                distance_to_medoids(a, &medoids)
+                    .partial_cmp(&distance_to_medoids(b, &medoids))
+                    .unwrap_or(cmp::Ordering::Equal)
+            })
+            .unwrap();
+        medoids.push(farthest);
+    }
+    let assign = |medoids: &[usize]| -> Vec<Vec<usize>> {
+        let mut clusters = vec![Vec::new(); medoids.len()];
+        for &city in &all_cities {
+            // Medoids always belong to their own cluster.
+            let nearest = match medoids.iter().position(|&medoid| medoid == city) {
+                Some(own) => own,
+                None => (0..medoids.len())
+                    .min_by(|&a, &b| {
+                        distances[medoids[a]][city]
+                            .partial_cmp(&distances[medoids[b]][city])
+                            .unwrap_or(cmp::Ordering::Equal)
+                    })
+                    .unwrap(),
+            };
+            clusters[nearest].push(city);
+        }
+        clusters
+    };
+    // Every update strictly decreases the total distance, the bound only
+    // guards against rounding issues.
+    for _ in 0..n_cities * k {
+        let clusters = assign(&medoids);
+        let updated = clusters
+            .iter()
+            .zip(medoids.iter())
+            .map(|(members, &medoid)| {
+                members
+                    .iter()
+                    .copied()
+                    .filter(|&candidate| {
+                        total_distance(candidate, members) < total_distance(medoid, members)
+                    })
+                    .min_by(|&a, &b| {
+                        total_distance(a, members)
+                            .partial_cmp(&total_distance(b, members))
+                            .unwrap_or(cmp::Ordering::Equal)
+                    })
+                    .unwrap_or(medoid)
+            })
+            .collect::<Vec<usize>>();
+        if updated == medoids {
+            return Ok(Clustering { medoids, clusters });
+        }
+        medoids = updated;
+    }
+    let clusters = assign(&medoids);
+    Ok(Clustering { medoids, clusters })
+}
+
+/// Solve large instances cluster-first, route-second: the cities are split
+/// into `k` clusters with `k_medoids`, a sub-tour is solved for every cluster,
+/// the clusters are ordered by solving the tour over their medoids and the
+/// sub-tours are joined in that order. Each sub-tour is entered at the city
+/// closest to the last city of the previous one.
+///
+/// # Arguments
+///
+/// * `distances` - The distances between all cities.
+/// * `k` - The number of clusters.
+/// * `config` - The configuration used for every sub-problem.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::euclidean_matrix_3d(&[
+///     [0.0, 0.0, 0.0],
+///     [1.0, 0.0, 0.0],
+///     [10.0, 0.0, 0.0],
+///     [11.0, 0.0, 0.0],
+/// ]);
+/// let config = tsp_solver::SolverConfig {
+///     n_generations: 10,
+///     ..Default::default()
+/// };
+/// let route = tsp_solver::cluster_solve(&distances, 2, &config).unwrap();
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 22.0);
+/// ```
+pub fn cluster_solve(
+    distances: &[Vec<f64>],
+    k: usize,
+    config: &SolverConfig,
+) -> Result<Vec<usize>, String> {
+    validate_config(config)?;
+    let clustering = k_medoids(distances, k)?;
+    // Tours over up to three cities cannot be improved on symmetric matrices.
+    let solve_subset = |cities: &[usize]| -> Result<Vec<usize>, String> {
+        if cities.len() <= 3 {
+            return Ok(cities.to_vec());
+        }
+        let best = solve_tsp_core(&submatrix(distances, cities)?, config)
+            .routes
+            .into_iter()
+            .next()
+            .ok_or_else(|| String::from("The solver returned no route."))?;
+        Ok(best.indexes.iter().map(|&index| cities[index]).collect())
+    };
+    let cluster_order = solve_subset(&clustering.medoids)?;
+    let mut route: Vec<usize> = Vec::with_capacity(distances.len());
+    for medoid in cluster_order {
+        let cluster = clustering
+            .medoids
+            .iter()
+            .position(|&candidate| candidate == medoid)
+            .unwrap();
+        let sub_tour = solve_subset(&clustering.clusters[cluster])?;
+        let entry = match route.last() {
+            Some(&last) => (0..sub_tour.len())
+                .min_by(|&a, &b| {
+                    distances[last][sub_tour[a]]
+                        .partial_cmp(&distances[last][sub_tour[b]])
+                        .unwrap_or(cmp::Ordering::Equal)
+                })
+                .unwrap(),
+            None => 0,
+        };
+        route.extend(sub_tour[entry..].iter().chain(sub_tour[..entry].iter()));
+    }
+    Ok(route)
+}
+
+/// Insert `city` into the closed tour `route` at the position that adds the
+/// least distance. The route may visit only a subset of the cities.
+///
+/// # Arguments
+///
+/// * `distances` - The distances between all cities.
+/// * `route` - The tour to extend, which must not visit `city` yet.
+/// * `city` - The city to insert.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::euclidean_matrix_3d(&[
+///     [0.0, 0.0, 0.0],
+///     [2.0, 0.0, 0.0],
+///     [1.0, 0.0, 0.0],
+/// ]);
+/// let (route, position, added) = tsp_solver::cheapest_insertion(&distances, &[0, 1], 2).unwrap();
+/// assert_eq!((route, position, added), (vec![0, 2, 1], 1, 0.0));
+/// ```
+pub fn cheapest_insertion(
+    distances: &[Vec<f64>],
+    route: &[usize],
+    city: usize,
+) -> Result<(Vec<usize>, usize, f64), String> {
+    validate_partial_route(route, distances.len())?;
+    if city >= distances.len() {
+        return Err(format!("City {} does not exist.", city));
+    }
+    if route.contains(&city) {
+        return Err(format!("City {} is already part of the route.", city));
+    }
+    // Inserting at `position` places the city between `route[position - 1]`
+    // and `route[position]`, the last position closes the tour.
+    let (position, added) = (1..=route.len())
+        .map(|position| {
+            let before = route[position - 1];
+            let after = route[position % route.len()];
+            let added = distances[before][city] + distances[city][after] - distances[before][after];
+            (position, added)
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(cmp::Ordering::Equal))
+        .unwrap_or((0, 0.0));
+    let mut new_route = route.to_vec();
+    new_route.insert(position, city);
+    Ok((new_route, position, added))
+}
+
+/// Remove `city` from the closed tour `route` and connect its neighbours.
+/// Returns the shortened route and the distance that was saved.
+///
+/// # Arguments
+///
+/// * `distances` - The distances between all cities.
+/// * `route` - The tour that visits `city`.
+/// * `city` - The city to remove.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::euclidean_matrix_3d(&[
+///     [0.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0],
+///     [1.0, 0.0, 0.0],
+/// ]);
+/// let (route, saved) = tsp_solver::remove_city(&distances, &[0, 1, 2], 1).unwrap();
+/// assert_eq!(route, vec![0, 2]);
+/// assert!((saved - 2.0_f64.sqrt()).abs() < 1e-9);
+/// ```
+pub fn remove_city(
+    distances: &[Vec<f64>],
+    route: &[usize],
+    city: usize,
+) -> Result<(Vec<usize>, f64), String> {
+    validate_partial_route(route, distances.len())?;
+    let position = route
+        .iter()
+        .position(|&visited| visited == city)
+        .ok_or_else(|| format!("City {} is not part of the route.", city))?;
+    let mut new_route = route.to_vec();
+    new_route.remove(position);
+    Ok((
+        new_route.clone(),
+        route_distance(distances, route) - route_distance(distances, &new_route),
+    ))
+}
+
+/// Check that `route` visits each of its cities at most once and only
+/// cities of a matrix with `n_cities` cities.
+fn validate_partial_route(route: &[usize], n_cities: usize) -> Result<(), String> {
+    let mut seen = vec![false; n_cities];
+    for &city in route {
+        if city >= n_cities {
+            return Err(format!("City {} does not exist.", city));
+        }
+        if seen[city] {
+            return Err(format!("City {} is visited more than once.", city));
+        }
+        seen[city] = true;
+    }
+    Ok(())
+}
+
+/// Find the generation in which the best distance improved for the last
+/// time, if the run plateaued afterwards. A run plateaued if there was no
+/// improvement for at least `fraction` of all generations.
+///
+/// # Arguments
+///
+/// * `best_history` - The best distance after every generation.
+/// * `fraction` - The share of generations without improvement that counts
+///   as a plateau.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let history = [9.0, 7.0, 5.0, 5.0, 5.0, 5.0];
+/// assert_eq!(tsp_solver::plateau_generation(&history, 0.5), Some(2));
+/// assert_eq!(tsp_solver::plateau_generation(&history, 0.8), None);
+/// ```
+pub fn plateau_generation(best_history: &[f64], fraction: f64) -> Option<usize> {
+    let last_improvement = best_history
+        .windows(2)
+        .rposition(|pair| pair[1] < pair[0])
+        .map_or(0, |position| position + 1);
+    let generations_without_improvement = best_history.len().saturating_sub(last_improvement + 1);
+    if !best_history.is_empty()
+        && generations_without_improvement as f64 >= fraction * best_history.len() as f64
+    {
+        Some(last_improvement)
+    } else {
+        None
+    }
+}
+
+/// Linearly rescale the off-diagonal distances into `[min, max]`, so that
+/// the shortest distance becomes `min` and the longest `max`. The diagonal
+/// stays zero. If all off-diagonal distances are equal they become `min`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 2.0, 4.0], vec![2.0, 0.0, 6.0], vec![4.0, 6.0, 0.0]];
+/// let normalized = tsp_solver::normalize_to(&distances, 1.0, 3.0).unwrap();
+/// assert_eq!(normalized, vec![vec![0.0, 1.0, 2.0], vec![1.0, 0.0, 3.0], vec![2.0, 3.0, 0.0]]);
+/// ```
+pub fn normalize_to(distances: &[Vec<f64>], min: f64, max: f64) -> Result<Vec<Vec<f64>>, String> {
+    if !(min.is_finite() && max.is_finite() && 0.0 <= min && min <= max) {
+        return Err(String::from("min and max have to satisfy 0 <= min <= max."));
+    }
+    validate_distance_matrix(distances)?;
+    let off_diagonal = || {
+        distances.iter().enumerate().flat_map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |&(to, _)| to != from)
+                .map(|(_, &distance)| distance)
+        })
+    };
+    let shortest = off_diagonal().fold(f64::INFINITY, f64::min);
+    let longest = off_diagonal().fold(f64::NEG_INFINITY, f64::max);
+    let scale = if longest > shortest {
+        (max - min) / (longest - shortest)
+    } else {
+        0.0
+    };
+    Ok(distances
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(to, &distance)| {
+                    if from == to {
+                        0.0
+                    } else {
+                        min + (distance - shortest) * scale
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Move the segment `route[i..i + length]` of a closed tour to directly after
+/// the city `route[k]`, keeping the order of the segment. `route[k]` must not
+/// be part of the segment.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::or_opt_move(&[0, 1, 2, 3, 4], 1, 2, 3), vec![0, 3, 1, 2, 4]);
+/// ```
+pub fn or_opt_move(route: &[usize], i: usize, length: usize, k: usize) -> Vec<usize> {
+    let segment = &route[i..i + length];
+    let mut moved = route[..i]
+        .iter()
+        .chain(route[i + length..].iter())
+        .copied()
+        .collect::<Vec<usize>>();
+    let after = moved.iter().position(|&city| city == route[k]).unwrap() + 1;
+    moved.splice(after..after, segment.iter().copied());
+    moved
+}
+
+/// Find the Or-opt move `(i, length, k, delta)` that shortens the closed tour
+/// `route` the most, or `None` if no move improves it. An Or-opt move takes
+/// a segment of one to three consecutive cities and reinserts it, in the same
+/// direction, between two other neighbouring cities, see `or_opt_move`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::euclidean_matrix_3d(&[
+///     [0.0, 0.0, 0.0],
+///     [1.0, 0.0, 0.0],
+///     [2.0, 0.0, 0.0],
+///     [3.0, 0.0, 0.0],
+///     [3.0, 1.0, 0.0],
+/// ]);
+/// let (i, length, k, _) = tsp_solver::best_or_opt_move(&distances, &[0, 2, 1, 3, 4]).unwrap();
+/// assert_eq!(tsp_solver::or_opt_move(&[0, 2, 1, 3, 4], i, length, k), vec![0, 1, 2, 3, 4]);
+/// ```
+pub fn best_or_opt_move(
+    distances: &[Vec<f64>],
+    route: &[usize],
+) -> Option<(usize, usize, usize, f64)> {
+    let n_cities = route.len();
+    let mut best_move = None;
+    let mut best_delta = -1e-9;
+    for length in 1..=3.min(n_cities.saturating_sub(2)) {
+        for i in 0..=(n_cities - length) {
+            let first = route[i];
+            let last = route[i + length - 1];
+            let before = route[(i + n_cities - 1) % n_cities];
+            let after = route[(i + length) % n_cities];
+            let removed =
+                distances[before][first] + distances[last][after] - distances[before][after];
+            for k in 0..n_cities {
+                let next = (k + 1) % n_cities;
+                // Both ends of the new position have to lie outside the
+                // segment.
+                let in_segment = |position: usize| (i..i + length).contains(&position);
+                if in_segment(k) || in_segment(next) {
+                    continue;
+                }
+                let delta = distances[route[k]][first] + distances[last][route[next]]
+                    - distances[route[k]][route[next]]
+                    - removed;
+                if delta < best_delta {
+                    best_delta = delta;
+                    best_move = Some((i, length, k, delta));
+                }
+            }
+        }
+    }
+    best_move
+}
+
+/// Apply the best 2-opt and Or-opt moves to the closed tour `route` until
+/// neither improves it any further. Returns the locally optimal route, the
+/// number of moves applied and the distance that was saved.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::euclidean_matrix_3d(&[
+///     [0.0, 0.0, 0.0],
+///     [1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0],
+///     [0.0, 1.0, 0.0],
+/// ]);
+/// let (route, n_moves, improvement) = tsp_solver::polish(&distances, &[0, 2, 1, 3]);
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 4.0);
+/// assert_eq!(n_moves, 1);
+/// assert!(improvement > 0.0);
+/// ```
+pub fn polish(distances: &[Vec<f64>], route: &[usize]) -> (Vec<usize>, usize, f64) {
+    let mut polished = route.to_vec();
+    let mut n_moves = 0;
+    loop {
+        if let Some((i, j, _)) = best_two_opt_move(distances, &polished) {
+            polished = two_opt_move(&polished, i, j);
+        } else if let Some((i, length, k, _)) = best_or_opt_move(distances, &polished) {
+            polished = or_opt_move(&polished, i, length, k);
+        } else {
+            break;
+        }
+        n_moves += 1;
+    }
+    let improvement = route_distance(distances, route) - route_distance(distances, &polished);
+    (polished, n_moves, improvement)
+}
+
+/// Improve a closed tour with the best Or-opt move until no move shortens
+/// it any further. Returns the improved route and the number of moves.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// let (route, n_moves) = tsp_solver::or_opt(&distances, &[0, 2, 1, 3]);
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 4.0);
+/// assert_eq!(n_moves, 1);
+/// ```
+pub fn or_opt(distances: &[Vec<f64>], route: &[usize]) -> (Vec<usize>, usize) {
+    let mut improved = route.to_vec();
+    let mut n_moves = 0;
+    while let Some((i, length, k, _)) = best_or_opt_move(distances, &improved) {
+        improved = or_opt_move(&improved, i, length, k);
+        n_moves += 1;
+    }
+    (improved, n_moves)
+}
+
+/// Round every off-diagonal distance to the nearest multiple of
+/// `bucket_size`. The diagonal stays zero.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 12.0], vec![17.0, 0.0]];
+/// let quantized = tsp_solver::quantize(&distances, 10.0).unwrap();
+/// assert_eq!(quantized, vec![vec![0.0, 10.0], vec![20.0, 0.0]]);
+/// ```
+pub fn quantize(distances: &[Vec<f64>], bucket_size: f64) -> Result<Vec<Vec<f64>>, String> {
+    if !(bucket_size.is_finite() && bucket_size > 0.0) {
+        return Err(String::from("bucket_size has to be positive."));
+    }
+    validate_distance_matrix(distances)?;
+    Ok(distances
+        .iter()
+        .enumerate()
+        .map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(to, distance)| {
+                    if from == to {
+                        0.0
+                    } else {
+                        (distance / bucket_size).round() * bucket_size
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Store a distance matrix compactly as integers: every distance becomes
+/// the nearest integer multiple of `scale`, so that it can be recovered up
+/// to `scale / 2`. Without a scale the largest distance is mapped to
+/// `u32::MAX`. Returns the scale and the integers.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 1.26], vec![2.5, 0.0]];
+/// let (scale, ints) = tsp_solver::compress(&distances, Some(0.1)).unwrap();
+/// assert_eq!(ints, vec![vec![0, 13], vec![25, 0]]);
+/// assert_eq!(tsp_solver::decompress(scale, &ints)[1][0], 2.5);
+/// ```
+pub fn compress(
+    distances: &[Vec<f64>],
+    scale: Option<f64>,
+) -> Result<(f64, Vec<Vec<u32>>), String> {
+    validate_distance_matrix(distances)?;
+    let max_distance = distances.iter().flatten().cloned().fold(0.0, f64::max);
+    let scale = match scale {
+        Some(scale) => scale,
+        None if max_distance > 0.0 => max_distance / f64::from(u32::MAX),
+        None => 1.0,
+    };
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(String::from("scale has to be positive."));
+    }
+    if (max_distance / scale).round() > f64::from(u32::MAX) {
+        return Err(format!(
+            "The scale {} is too small for the largest distance {}.",
+            scale, max_distance
+        ));
+    }
+    let ints = distances
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|distance| (distance / scale).round() as u32)
+                .collect()
+        })
+        .collect();
+    Ok((scale, ints))
+}
+
+/// Recover a distance matrix from the integers and the scale of `compress`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::decompress(0.5, &[vec![0, 3], vec![4, 0]]), vec![vec![0.0, 1.5], vec![2.0, 0.0]]);
+/// ```
+pub fn decompress(scale: f64, ints: &[Vec<u32>]) -> Vec<Vec<f64>> {
+    ints.iter()
+        .map(|row| row.iter().map(|&int| f64::from(int) * scale).collect())
+        .collect()
+}
+
+/// Compute the distance of the open path `route[start_index..=end_index]`,
+/// without returning to its start.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 1.0, 4.0], vec![1.0, 0.0, 2.0], vec![4.0, 2.0, 0.0]];
+/// assert_eq!(tsp_solver::segment_distance(&distances, &[0, 1, 2], 0, 2), Ok(3.0));
+/// let jagged = vec![vec![0.0, 1.0, 4.0], vec![1.0, 0.0], vec![4.0, 2.0, 0.0]];
+/// assert!(tsp_solver::segment_distance(&jagged, &[0, 1, 2], 0, 2).is_err());
+/// ```
+pub fn segment_distance(
+    distances: &[Vec<f64>],
+    route: &[usize],
+    start_index: usize,
+    end_index: usize,
+) -> Result<f64, String> {
+    validate_distance_matrix(distances)?;
+    validate_route(route, distances.len())?;
+    if start_index > end_index || end_index >= route.len() {
+        return Err(format!(
+            "The segment has to satisfy start_index <= end_index < {}.",
+            route.len()
+        ));
+    }
+    Ok(route[start_index..=end_index]
+        .windows(2)
+        .map(|leg| distances[leg[0]][leg[1]])
+        .sum())
+}
+
+/// Build a symmetric distance matrix of `n_cities` cities from undirected,
+/// weighted edges `(a, b, weight)`. Pairs without an edge are set to
+/// `sentinel`, e.g. to fill them in with `complete_missing` afterwards.
+///
+/// # Arguments
+///
+/// * `n_cities` - The number of cities.
+/// * `edges` - The undirected edges with their weight.
+/// * `sentinel` - The value of pairs of cities without an edge.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::undirected_edges_to_matrix(3, &[(0, 1, 2.0), (2, 1, 3.0)], -1.0);
+/// assert_eq!(
+///     distances,
+///     Ok(vec![vec![0.0, 2.0, -1.0], vec![2.0, 0.0, 3.0], vec![-1.0, 3.0, 0.0]])
+/// );
+/// assert!(tsp_solver::undirected_edges_to_matrix(3, &[], f64::NAN).is_err());
+/// ```
+pub fn undirected_edges_to_matrix(
+    n_cities: usize,
+    edges: &[(usize, usize, f64)],
+    sentinel: f64,
+) -> Result<Vec<Vec<f64>>, String> {
+    if !sentinel.is_finite() {
+        return Err(String::from("The sentinel has to be finite."));
+    }
+    let mut distances = vec![vec![sentinel; n_cities]; n_cities];
+    let mut weights: HashMap<(usize, usize), f64> = HashMap::new();
+    for &(a, b, weight) in edges {
+        if a >= n_cities || b >= n_cities || a == b {
+            return Err(format!(
+                "The edge [{}, {}] has to connect two different existing cities.",
+                a, b
+            ));
+        }
+        if !(weight.is_finite() && weight >= 0.0) {
+            return Err(format!(
+                "The weight of the edge [{}, {}] has to be non-negative.",
+                a, b
+            ));
+        }
+        let previous = *weights.entry((a.min(b), a.max(b))).or_insert(weight);
+        if previous != weight {
+            return Err(format!(
+                "The edge [{}, {}] is given with the weights {} and {}.",
+                a, b, previous, weight
+            ));
+        }
+        distances[a][b] = weight;
+        distances[b][a] = weight;
+    }
+    for (city, row) in distances.iter_mut().enumerate() {
+        row[city] = 0.0;
+    }
+    Ok(distances)
+}
+
+/// Summary statistics of a sample of tour distances.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DistanceStats {
+    /// The mean distance.
+    pub mean: f64,
+    /// The population standard deviation of the distances.
+    pub std: f64,
+    /// The shortest distance.
+    pub min: f64,
+    /// The longest distance.
+    pub max: f64,
+}
+
+/// The distribution of the distances of `samples` uniformly random closed
+/// tours, to compare the result of a solver against. Tours are drawn with
+/// replacement, so the same tour can be counted more than once.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+/// use rand::SeedableRng;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// // Every tour through three cities has the same length.
+/// let stats = tsp_solver::random_baseline(&distances, 10, &mut rng);
+/// assert_eq!((stats.mean, stats.std, stats.min, stats.max), (6.0, 0.0, 6.0, 6.0));
+/// ```
+pub fn random_baseline<R: Rng>(
+    distances: &[Vec<f64>],
+    samples: usize,
+    rng: &mut R,
+) -> DistanceStats {
+    let mut indexes = (0..distances.len()).collect::<Vec<usize>>();
+    let tour_distances = (0..samples)
+        .map(|_| {
+            indexes.shuffle(rng);
+            route_distance(distances, &indexes)
+        })
+        .collect::<Vec<f64>>();
+    let mean = tour_distances.iter().sum::<f64>() / samples as f64;
+    let variance = tour_distances
+        .iter()
+        .map(|distance| (distance - mean).powi(2))
+        .sum::<f64>()
+        / samples as f64;
+    DistanceStats {
+        mean,
+        std: variance.sqrt(),
+        min: tour_distances.iter().cloned().fold(f64::INFINITY, f64::min),
+        max: tour_distances
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max),
+    }
+}
+
+/// Transpose a distance matrix, so that the distance from `a` to `b` of the
+/// result is the distance from `b` to `a` of `distances`. A tour on the
+/// transposed matrix is as long as the reversed tour on the original one.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![0.0, 1.0], vec![2.0, 0.0]];
+/// assert_eq!(tsp_solver::transpose(&distances), vec![vec![0.0, 2.0], vec![1.0, 0.0]]);
+/// ```
+pub fn transpose(distances: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    (0..distances.len())
+        .map(|to| distances.iter().map(|row| row[to]).collect())
+        .collect()
+}
+
+/// Find an optimal closed tour by trying all tours that start at city 0.
+/// This takes `(n - 1)!` steps, so only use it for a handful of cities.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 5.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 5.0],
+///     vec![5.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 5.0, 1.0, 0.0],
+/// ];
+/// let route = tsp_solver::brute_force_route(&distances);
+/// assert_eq!(tsp_solver::route_distance(&distances, &route), 4.0);
+/// ```
+pub fn brute_force_route(distances: &[Vec<f64>]) -> Vec<usize> {
+    /// Try all orders of `route[fixed..]` and keep the shortest tour.
+    fn permute(
+        distances: &[Vec<f64>],
+        route: &mut Vec<usize>,
+        fixed: usize,
+        best: &mut (Vec<usize>, f64),
+    ) {
+        if fixed == route.len() {
+            let distance = route_distance(distances, route);
+            if distance < best.1 {
+                *best = (route.clone(), distance);
+            }
+            return;
+        }
+        for position in fixed..route.len() {
+            route.swap(fixed, position);
+            permute(distances, route, fixed + 1, best);
+            route.swap(fixed, position);
+        }
+    }
+    let mut route = (0..distances.len()).collect::<Vec<usize>>();
+    let mut best = (route.clone(), f64::INFINITY);
+    permute(distances, &mut route, 1.min(distances.len()), &mut best);
+    best.0
+}
+
+/// Down-sample `values` to at most `max_points` values that are spread
+/// uniformly over them. The first and the last value are kept if
+/// `max_points` is at least 2, and the last one if it is 1.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let values = (0..10).collect::<Vec<usize>>();
+/// assert_eq!(tsp_solver::downsample(&values, 4), vec![0, 3, 6, 9]);
+/// assert_eq!(tsp_solver::downsample(&values, 1), vec![9]);
+/// assert_eq!(tsp_solver::downsample(&values, 20), values);
+/// ```
+pub fn downsample<T: Clone>(values: &[T], max_points: usize) -> Vec<T> {
+    match max_points {
+        _ if values.len() <= max_points => values.to_vec(),
+        0 => vec![],
+        1 => values[values.len() - 1..].to_vec(),
+        _ => (0..max_points)
+            .map(|point| values[point * (values.len() - 1) / (max_points - 1)].clone())
+            .collect(),
+    }
+}
+
+/// Set the diagonal of a distance matrix to zero, leaving all other
+/// entries as they are. Rows that are too short to have a diagonal entry
+/// are kept as they are.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![5.0, 1.0], vec![2.0, -1.0]];
+/// assert_eq!(tsp_solver::zero_diagonal(&distances), vec![vec![0.0, 1.0], vec![2.0, 0.0]]);
+/// ```
+pub fn zero_diagonal(distances: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let mut zeroed = distances.to_vec();
+    for (city, row) in zeroed.iter_mut().enumerate() {
+        if let Some(distance) = row.get_mut(city) {
+            *distance = 0.0;
+        }
+    }
+    zeroed
+}
+
+/// Convert candidate lists given as `(city, [candidate, ...])`-pairs into
+/// the candidates of every city, as used by `candidate_two_opt`. Cities
+/// that are listed more than once get all their candidates.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let candidates = tsp_solver::candidate_lists(3, &[(0, vec![1]), (2, vec![0, 1])]).unwrap();
+/// assert_eq!(candidates, vec![vec![1], vec![], vec![0, 1]]);
+/// assert!(tsp_solver::candidate_lists(3, &[(0, vec![3])]).is_err());
+/// assert!(tsp_solver::candidate_lists(3, &[(1, vec![1])]).is_err());
+/// ```
+pub fn candidate_lists(
+    n_cities: usize,
+    pairs: &[(usize, Vec<usize>)],
+) -> Result<Vec<Vec<usize>>, String> {
+    let mut candidates = vec![Vec::new(); n_cities];
+    for (city, list) in pairs {
+        for &candidate in list {
+            if *city >= n_cities || candidate >= n_cities {
+                return Err(format!(
+                    "The candidate {} of city {} is out of range for {} cities.",
+                    candidate, city, n_cities
+                ));
+            }
+            if candidate == *city {
+                return Err(format!("City {} cannot be its own candidate.", city));
+            }
+            candidates[*city].push(candidate);
+        }
+    }
+    Ok(candidates)
+}
+
+/// The permutation matrix of a route: entry `(position, city)` is 1 if the
+/// route visits `city` at `position` and 0 otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(
+///     tsp_solver::permutation_matrix(&[2, 0, 1]),
+///     vec![vec![0, 0, 1], vec![1, 0, 0], vec![0, 1, 0]]
+/// );
+/// ```
+pub fn permutation_matrix(route: &[usize]) -> Vec<Vec<u8>> {
+    route
+        .iter()
+        .map(|&city| {
+            let mut row = vec![0; route.len()];
+            row[city] = 1;
+            row
+        })
+        .collect()
+}
+
+/// The strict upper triangle of a distance matrix as jagged rows: row `i`
+/// holds the distances from city `i` to the cities `i + 1..n`. The lower
+/// triangle and the diagonal are dropped, so only symmetric matrices with a
+/// zero diagonal can be rebuilt with `from_upper_triangle`.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// assert_eq!(
+///     tsp_solver::upper_triangle(&distances),
+///     vec![vec![1.0, 2.0], vec![3.0], vec![]]
+/// );
+/// ```
+pub fn upper_triangle(distances: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    distances
+        .iter()
+        .enumerate()
+        .map(|(from, row)| row[from + 1..].to_vec())
+        .collect()
+}
+
+/// Rebuild the symmetric distance matrix with a zero diagonal from its
+/// upper triangle, see `upper_triangle`. The last row may be omitted.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = tsp_solver::from_upper_triangle(&[vec![1.0, 2.0], vec![3.0]]).unwrap();
+/// assert_eq!(
+///     distances,
+///     vec![
+///         vec![0.0, 1.0, 2.0],
+///         vec![1.0, 0.0, 3.0],
+///         vec![2.0, 3.0, 0.0],
+///     ]
+/// );
+/// assert!(tsp_solver::from_upper_triangle(&[vec![1.0], vec![3.0]]).is_err());
+/// ```
+pub fn from_upper_triangle(triangle: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, String> {
+    let n_cities = match triangle.first() {
+        Some(first) => first.len() + 1,
+        None => return Err(String::from("The triangle is empty.")),
+    };
+    if triangle.len() > n_cities {
+        return Err(format!(
+            "The triangle of {} cities has at most {} rows, but {} were given.",
+            n_cities,
+            n_cities,
+            triangle.len()
+        ));
+    }
+    let mut distances = vec![vec![0.0; n_cities]; n_cities];
+    for (from, row) in triangle.iter().enumerate() {
+        if row.len() != n_cities - from - 1 {
+            return Err(format!(
+                "Row {} of the triangle has to have {} entries, but has {}.",
+                from,
+                n_cities - from - 1,
+                row.len()
+            ));
+        }
+        for (offset, &distance) in row.iter().enumerate() {
+            let to = from + 1 + offset;
+            distances[from][to] = distance;
+            distances[to][from] = distance;
+        }
+    }
+    validate_distance_matrix(&distances)?;
+    Ok(distances)
+}
+
+/// Write a closed tour through `[latitude, longitude]`-points as a GPX 1.1
+/// track. The track returns to its first point, so it has one point more
+/// than the route.
+///
+/// # Examples
 ///
-/// * `distance_matrix` - These distances define the fitness of an invidual.
-/// * `n_generation` - How many generations should the algorithm run for?
-/// * `n_routes` - How many routes should be kept in the population.
-/// * `n_random_route_per_generation` - How many random routes should be
-///     ingested in every generation to allow?
-pub fn solve_tsp(
-    distance_matrix: &distance_mat::DistanceMat,
-    n_generations: usize,
-    n_routes: usize,
-    n_random_individuals_per_generation: usize,
-    top_n: usize,
-) -> Vec<route::Route> {
-    let initial_population = distance_matrix.get_random_population(n_routes);
-    // Decay mutation probability.
-    (0..10000)
-        .step_by(10000 / n_generations)
-        .fold(
-            initial_population,
-            |population, mutation_probability_int| {
-                population
-                    .evolve(1.0 - (f64::from(mutation_probability_int) / 10000.0) as f32)
-                    // Add a few random inidividuals each round.
-                    .add_n_random_nodes(n_random_individuals_per_generation)
-                    .get_fittest_population(n_routes, distance_matrix)
-            },
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let gpx = tsp_solver::route_to_gpx(&[1, 0], &[[52.5, 13.4], [48.1, 11.6]]);
+/// assert_eq!(gpx.matches("<trkpt").count(), 3);
+/// assert!(gpx.contains(r#"<trkpt lat="48.1" lon="11.6"/>"#));
+/// ```
+pub fn route_to_gpx(route: &[usize], points: &[[f64; 2]]) -> String {
+    let mut gpx = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<gpx version="1.1" creator="genetic-algorithm-tsp-api" xmlns="http://www.topografix.com/GPX/1/1">"#,
+        "\n  <trk>\n    <trkseg>\n"
+    ));
+    for &city in route.iter().chain(route.first()) {
+        let [lat, lon] = points[city];
+        gpx.push_str(&format!("      <trkpt lat=\"{}\" lon=\"{}\"/>\n", lat, lon));
+    }
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    gpx
+}
+
+/// Relative difference below which two directions of a pair count as
+/// almost symmetric in `validation_warnings`.
+pub const NEAR_SYMMETRY_TOLERANCE: f64 = 1e-6;
+
+/// Collect the issues of a valid distance matrix and, for coordinate
+/// inputs, its `points` that do not prevent solving but likely point to a
+/// mistake in the input: a non-zero diagonal, a matrix that is symmetric
+/// up to `NEAR_SYMMETRY_TOLERANCE` but not exactly, and duplicate points.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![vec![1.0, 2.0], vec![2.0, 0.0]];
+/// assert_eq!(tsp_solver::validation_warnings(&distances, None).len(), 1);
+/// let points = vec![vec![0.0, 1.0], vec![0.0, 1.0]];
+/// let distances = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+/// assert_eq!(tsp_solver::validation_warnings(&distances, Some(&points)).len(), 1);
+/// ```
+pub fn validation_warnings(distances: &[Vec<f64>], points: Option<&[Vec<f64>]>) -> Vec<String> {
+    let mut warnings = distances
+        .iter()
+        .enumerate()
+        .filter(|(city, row)| row[*city] != 0.0)
+        .map(|(city, row)| {
+            format!(
+                "The distance from {} to itself is {}, not 0.",
+                city, row[city]
+            )
+        })
+        .collect::<Vec<String>>();
+    let largest = distances.iter().flatten().cloned().fold(0.0, f64::max);
+    if !is_symmetric(distances, 0.0) && is_symmetric(distances, NEAR_SYMMETRY_TOLERANCE * largest) {
+        warnings.push(String::from(
+            "The matrix is almost, but not exactly symmetric.",
+        ));
+    }
+    let points = points.unwrap_or_default();
+    for (a, point) in points.iter().enumerate() {
+        if let Some(b) = points[..a].iter().position(|other| other == point) {
+            warnings.push(format!("The points {} and {} are the same.", b, a));
+        }
+    }
+    warnings
+}
+
+/// Add a dummy city to a distance matrix so that the shortest closed tour
+/// through the result is the shortest open path from `start` to `end`
+/// around the dummy. The dummy is the last city; it is reached from `end`
+/// and left to `start` at no cost, all its other legs cost more than any
+/// path through the original cities.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// let augmented = tsp_solver::open_path_matrix(&distances, 0, 2).unwrap();
+/// assert_eq!(augmented.len(), 4);
+/// assert_eq!((augmented[2][3], augmented[3][0]), (0.0, 0.0));
+/// assert!(augmented[3][1] > 6.0);
+/// ```
+pub fn open_path_matrix(
+    distances: &[Vec<f64>],
+    start: usize,
+    end: usize,
+) -> Result<Vec<Vec<f64>>, String> {
+    validate_distance_matrix(distances)?;
+    let n_cities = distances.len();
+    if start >= n_cities || end >= n_cities {
+        return Err(format!(
+            "start and end have to be cities below {}.",
+            n_cities
+        ));
+    }
+    if start == end && n_cities > 1 {
+        return Err(String::from(
+            "start and end have to differ, a path back to its start is a closed tour.",
+        ));
+    }
+    let penalty = distances.iter().flatten().sum::<f64>() + 1.0;
+    let mut augmented = distances.to_vec();
+    for (city, row) in augmented.iter_mut().enumerate() {
+        row.push(if city == end { 0.0 } else { penalty });
+    }
+    augmented.push(
+        (0..=n_cities)
+            .map(|city| match city {
+                _ if city == n_cities => 0.0,
+                _ if city == start => 0.0,
+                _ => penalty,
+            })
+            .collect(),
+    );
+    Ok(augmented)
+}
+
+/// Extract the open path from `start` to `end` from a closed tour through
+/// the matrix of `open_path_matrix`, dropping the dummy city. If the tour
+/// does not leave the dummy to `start` and return from `end`, these two
+/// cities are moved to the ends of the path.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::open_path_from_tour(&[1, 2, 3, 0], 0, 2), vec![0, 1, 2]);
+/// assert_eq!(tsp_solver::open_path_from_tour(&[2, 3, 0, 1], 0, 2), vec![0, 1, 2]);
+/// ```
+pub fn open_path_from_tour(tour: &[usize], start: usize, end: usize) -> Vec<usize> {
+    let dummy = tour.len() - 1;
+    let at = tour.iter().position(|&city| city == dummy).unwrap_or(0);
+    let path = tour[at + 1..]
+        .iter()
+        .chain(tour[..at].iter())
+        .cloned()
+        .collect::<Vec<usize>>();
+    if path.first() == Some(&start) && path.last() == Some(&end) {
+        return path;
+    }
+    let inner = path
+        .into_iter()
+        .filter(|&city| city != start && city != end);
+    std::iter::once(start)
+        .chain(inner)
+        .chain((start != end).then_some(end))
+        .collect()
+}
+
+/// A proposed edit of a tour. All indexes refer to positions in the route.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TourEdit {
+    /// Swap the cities at two positions.
+    Swap {
+        /// The position of the first city.
+        i: usize,
+        /// The position of the second city.
+        j: usize,
+    },
+    /// Move a city to another position, shifting the cities in between.
+    Move {
+        /// The position of the city to move.
+        from: usize,
+        /// The position of the city in the edited route.
+        to: usize,
+    },
+}
+
+/// Apply `edit` to `route`, whose positions have to be valid.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver::{self, TourEdit};
+///
+/// let route = [0, 1, 2, 3];
+/// assert_eq!(tsp_solver::apply_edit(&route, TourEdit::Swap { i: 0, j: 2 }), vec![2, 1, 0, 3]);
+/// assert_eq!(tsp_solver::apply_edit(&route, TourEdit::Move { from: 0, to: 2 }), vec![1, 2, 0, 3]);
+/// ```
+pub fn apply_edit(route: &[usize], edit: TourEdit) -> Vec<usize> {
+    let mut edited = route.to_vec();
+    match edit {
+        TourEdit::Swap { i, j } => edited.swap(i, j),
+        TourEdit::Move { from, to } => {
+            let city = edited.remove(from);
+            edited.insert(to, city);
+        }
+    }
+    edited
+}
+
+/// Compute the change of the distance of the closed tour `route` by
+/// `edit` from the edges it replaces only, without scoring the whole tour.
+/// A negative delta shortens the tour.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver::{self, TourEdit};
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0, 1.0],
+///     vec![1.0, 0.0, 1.0, 2.0],
+///     vec![2.0, 1.0, 0.0, 1.0],
+///     vec![1.0, 2.0, 1.0, 0.0],
+/// ];
+/// let delta = tsp_solver::edit_delta(&distances, &[0, 2, 1, 3], TourEdit::Swap { i: 1, j: 2 });
+/// assert_eq!(delta, Ok(-2.0));
+/// ```
+pub fn edit_delta(distances: &[Vec<f64>], route: &[usize], edit: TourEdit) -> Result<f64, String> {
+    validate_distance_matrix(distances)?;
+    validate_route(route, distances.len())?;
+    let n_cities = route.len();
+    let (first, second) = match edit {
+        TourEdit::Swap { i, j } => (i, j),
+        TourEdit::Move { from, to } => (from, to),
+    };
+    if first >= n_cities || second >= n_cities {
+        return Err(format!(
+            "The positions of the edit have to be below {}.",
+            n_cities
+        ));
+    }
+    if first == second {
+        return Ok(0.0);
+    }
+    match edit {
+        TourEdit::Swap { i, j } => {
+            // Only the legs leaving the positions before and at the two
+            // swapped cities change.
+            let mut starts = vec![
+                (i + n_cities - 1) % n_cities,
+                i,
+                (j + n_cities - 1) % n_cities,
+                j,
+            ];
+            starts.sort_unstable();
+            starts.dedup();
+            let edited = apply_edit(route, edit);
+            let leg =
+                |tour: &[usize], start: usize| distances[tour[start]][tour[(start + 1) % n_cities]];
+            Ok(starts
+                .iter()
+                .map(|&start| leg(&edited, start) - leg(route, start))
+                .sum())
+        }
+        TourEdit::Move { from, to } => {
+            // Remove the city between its neighbours, then insert it between
+            // the cities around its new position in the shortened tour.
+            let city = route[from];
+            let before = route[(from + n_cities - 1) % n_cities];
+            let after = route[(from + 1) % n_cities];
+            let removed =
+                distances[before][after] - distances[before][city] - distances[city][after];
+            let shortened = route
+                .iter()
+                .enumerate()
+                .filter(|&(position, _)| position != from)
+                .map(|(_, &visited)| visited)
+                .collect::<Vec<usize>>();
+            let rest = shortened.len();
+            let before = shortened[(to + rest - 1) % rest];
+            let after = shortened[to % rest];
+            let inserted =
+                distances[before][city] + distances[city][after] - distances[before][after];
+            Ok(removed + inserted)
+        }
+    }
+}
+
+/// Largest `k` of the k-opt neighborhoods `k_opt_neighbors` counts.
+pub const MAX_K_OPT: usize = 8;
+
+/// Count the pure reconnections of a k-opt move: the ways to reconnect the
+/// `k` segments left by removing `k` edges of a closed tour into a new
+/// tour that adds none of the removed edges back. The orders and
+/// orientations of the segments are enumerated, which takes
+/// `(k - 1)! * 2^(k - 1)` steps.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::pure_reconnections(2), 1);
+/// assert_eq!(tsp_solver::pure_reconnections(3), 4);
+/// ```
+pub fn pure_reconnections(k: usize) -> u64 {
+    /// Try all orders and orientations of `order[placed..]`, where segment
+    /// `i` ends in the nodes `2 * i` and `2 * i + 1`.
+    fn count(order: &mut Vec<usize>, placed: usize, exit: usize, k: usize) -> u64 {
+        // The removed edges connect the end of a segment with the start of
+        // the next one.
+        let removed = |a: usize, b: usize| {
+            let (low, high) = (a.min(b), a.max(b));
+            (high == low + 1 && low % 2 == 1) || (low == 0 && high == 2 * k - 1)
+        };
+        if placed == order.len() {
+            return u64::from(!removed(exit, 0));
+        }
+        let mut total = 0;
+        for position in placed..order.len() {
+            order.swap(placed, position);
+            let segment = order[placed];
+            for (entry, next_exit) in [
+                (2 * segment, 2 * segment + 1),
+                (2 * segment + 1, 2 * segment),
+            ] {
+                if !removed(exit, entry) {
+                    total += count(order, placed + 1, next_exit, k);
+                }
+            }
+            order.swap(placed, position);
+        }
+        total
+    }
+    if k < 2 {
+        return 0;
+    }
+    // The first segment is kept in place and orientation.
+    let mut order = (0..k).collect::<Vec<usize>>();
+    count(&mut order, 1, 1, k)
+}
+
+/// Count the k-opt neighbors of a closed tour through `n_cities` cities:
+/// the ways to remove `k` pairwise non-adjacent edges times the pure
+/// reconnections of the segments left.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert_eq!(tsp_solver::k_opt_neighbors(10, 2), Ok(35));
+/// assert_eq!(tsp_solver::k_opt_neighbors(5, 3), Ok(0));
+/// ```
+pub fn k_opt_neighbors(n_cities: usize, k: usize) -> Result<u64, String> {
+    if !(2..=MAX_K_OPT).contains(&k) {
+        return Err(format!("k has to be in [2, {}].", MAX_K_OPT));
+    }
+    if n_cities < 2 * k {
+        return Ok(0);
+    }
+    let too_many = || {
+        format!(
+            "The {}-opt neighborhood of {} cities is too large.",
+            k, n_cities
         )
-        .get_n_fittest(top_n, distance_matrix)
+    };
+    // Choosing `k` non-adjacent edges of a cycle with `n` edges can be done
+    // in `n / k * C(n - k - 1, k - 1)` ways.
+    let mut choices: u128 = 1;
+    for step in 0..(k - 1) {
+        choices = choices
+            .checked_mul((n_cities - k - 1 - step) as u128)
+            .ok_or_else(too_many)?
+            / (step + 1) as u128;
+    }
+    let removals = choices.checked_mul(n_cities as u128).ok_or_else(too_many)? / k as u128;
+    u64::try_from(removals * u128::from(pure_reconnections(k))).map_err(|_| too_many())
+}
+
+/// Relate the distance of a closed tour to the scale of its matrix by
+/// dividing it by the mean distance between two different cities times the
+/// number of cities, the expected distance of a random tour. Returns `None`
+/// if all distances between different cities are zero.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let distances = vec![
+///     vec![0.0, 1.0, 2.0],
+///     vec![1.0, 0.0, 3.0],
+///     vec![2.0, 3.0, 0.0],
+/// ];
+/// assert_eq!(tsp_solver::normalized_distance(&distances, 3.0), Some(0.5));
+/// assert_eq!(tsp_solver::normalized_distance(&[vec![0.0]], 0.0), None);
+/// ```
+pub fn normalized_distance(distances: &[Vec<f64>], distance: f64) -> Option<f64> {
+    let n_cities = distances.len();
+    let n_edges = n_cities * n_cities.saturating_sub(1);
+    let total = distances
+        .iter()
+        .enumerate()
+        .flat_map(|(from, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |&(to, _)| to != from)
+                .map(|(_, &distance)| distance)
+        })
+        .sum::<f64>();
+    let mean_edge = total / n_edges as f64;
+    (n_edges > 0 && mean_edge > 0.0).then(|| distance / (mean_edge * n_cities as f64))
+}
+
+/// Check whether the segments from `a` to `b` and from `c` to `d` in the
+/// plane have a point in common, including touching and overlapping
+/// collinear segments.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// assert!(tsp_solver::segments_intersect([0.0, 0.0], [1.0, 1.0], [0.0, 1.0], [1.0, 0.0]));
+/// assert!(!tsp_solver::segments_intersect([0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]));
+/// ```
+pub fn segments_intersect(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> bool {
+    // The sign of the cross product tells on which side of `from`-`to`
+    // the point lies.
+    let side = |from: [f64; 2], to: [f64; 2], point: [f64; 2]| {
+        let cross =
+            (to[0] - from[0]) * (point[1] - from[1]) - (to[1] - from[1]) * (point[0] - from[0]);
+        cross.partial_cmp(&0.0).unwrap_or(cmp::Ordering::Equal)
+    };
+    // Whether `point`, which is collinear with the segment, lies on it.
+    let within = |from: [f64; 2], to: [f64; 2], point: [f64; 2]| {
+        (0..2).all(|axis| {
+            point[axis] >= from[axis].min(to[axis]) && point[axis] <= from[axis].max(to[axis])
+        })
+    };
+    let (abc, abd) = (side(a, b, c), side(a, b, d));
+    let (cda, cdb) = (side(c, d, a), side(c, d, b));
+    if abc != abd && cda != cdb && ![abc, abd, cda, cdb].contains(&cmp::Ordering::Equal) {
+        return true;
+    }
+    (abc == cmp::Ordering::Equal && within(a, b, c))
+        || (abd == cmp::Ordering::Equal && within(a, b, d))
+        || (cda == cmp::Ordering::Equal && within(c, d, a))
+        || (cdb == cmp::Ordering::Equal && within(c, d, b))
+}
+
+/// Count the pairs of non-adjacent legs of the closed tour `route` through
+/// `points` in the plane that intersect. A shortest euclidean tour has
+/// none.
+///
+/// # Examples
+///
+/// ```
+/// use genetic_algorithm_tsp_api::tsp_solver;
+///
+/// let square = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+/// assert_eq!(tsp_solver::count_crossings(&[0, 1, 2, 3], &square), 0);
+/// assert_eq!(tsp_solver::count_crossings(&[0, 2, 1, 3], &square), 1);
+/// ```
+pub fn count_crossings(route: &[usize], points: &[[f64; 2]]) -> usize {
+    let n_legs = route.len();
+    let leg = |index: usize| (points[route[index]], points[route[(index + 1) % n_legs]]);
+    let mut crossings = 0;
+    for first in 0..n_legs {
+        // The last leg is adjacent to the first one.
+        let last = if first == 0 { n_legs - 1 } else { n_legs };
+        for second in (first + 2)..last {
+            let ((a, b), (c, d)) = (leg(first), leg(second));
+            if segments_intersect(a, b, c, d) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
 }
 
 mod tests {
@@ -94,4 +3874,917 @@ mod tests {
         // Get a solution
         let _ = solve_tsp(&distances, 20, 10, 10, 3);
     }
+    #[test]
+    fn test_submatrix() {
+        use super::submatrix;
+        let distances = vec![
+            vec![0.0, 1.0, 2.0, 3.0],
+            vec![1.0, 0.0, 4.0, 5.0],
+            vec![2.0, 4.0, 0.0, 6.0],
+            vec![3.0, 5.0, 6.0, 0.0],
+        ];
+        // Every entry of the sub-matrix should be the corresponding entry
+        // of the original matrix.
+        let indices = vec![3, 0, 2];
+        let sub = submatrix(&distances, &indices).unwrap();
+        for (a, &from) in indices.iter().enumerate() {
+            for (b, &to) in indices.iter().enumerate() {
+                assert_eq!(sub[a][b], distances[from][to]);
+            }
+        }
+        // Duplicated or out-of-range indices are rejected.
+        assert!(submatrix(&distances, &[0, 0]).is_err());
+        assert!(submatrix(&distances, &[0, 4]).is_err());
+    }
+    #[test]
+    fn test_route_legs() {
+        use super::route_legs;
+        let distances = vec![
+            vec![0.0, 1.0, 2.0, 3.0],
+            vec![1.0, 0.0, 4.0, 5.0],
+            vec![2.0, 4.0, 0.0, 6.0],
+            vec![3.0, 5.0, 6.0, 0.0],
+        ];
+        // A closed tour has as many legs as cities and the legs sum up to
+        // the distance of the tour.
+        let legs = route_legs(&distances, &[0, 2, 1, 3]);
+        assert_eq!(legs.len(), 4);
+        assert_eq!(legs[3], (3, 0, 3.0));
+        assert_eq!(
+            legs.iter().map(|(_, _, distance)| distance).sum::<f64>(),
+            2.0 + 4.0 + 5.0 + 3.0
+        );
+    }
+    #[test]
+    fn test_euclidean_matrix_3d() {
+        use super::euclidean_matrix_3d;
+        let distances = euclidean_matrix_3d(&[[0.0, 0.0, 0.0], [3.0, 4.0, 0.0], [3.0, 4.0, 12.0]]);
+        assert_eq!(
+            distances,
+            vec![
+                vec![0.0, 5.0, 13.0],
+                vec![5.0, 0.0, 12.0],
+                vec![13.0, 12.0, 0.0]
+            ]
+        );
+    }
+    #[test]
+    fn test_floyd_warshall() {
+        use super::floyd_warshall;
+        let distances = vec![
+            vec![0.0, 1.0, 10.0, f64::INFINITY],
+            vec![1.0, 0.0, 2.0, f64::INFINITY],
+            vec![10.0, 2.0, 0.0, f64::INFINITY],
+            vec![f64::INFINITY, f64::INFINITY, f64::INFINITY, 0.0],
+        ];
+        let shortest = floyd_warshall(&distances);
+        // The detour over city 1 is shorter than the direct edge.
+        assert_eq!(shortest[0][2], 3.0);
+        // City 3 is not connected to any other city.
+        assert!(shortest[0][3].is_infinite());
+        assert_eq!(shortest[3][3], 0.0);
+    }
+    #[test]
+    fn test_complete_missing() {
+        use super::{complete_missing, FillStrategy};
+        let distances = vec![
+            vec![-1.0, 1.0, -1.0],
+            vec![1.0, 0.0, 2.0],
+            vec![-1.0, 4.0, 0.0],
+        ];
+        // No sentinel remains, whatever the strategy.
+        for strategy in [
+            FillStrategy::ShortestPath,
+            FillStrategy::Mean,
+            FillStrategy::Max,
+        ] {
+            let completed = complete_missing(&distances, -1.0, strategy).unwrap();
+            assert!(completed.iter().flatten().all(|&distance| distance != -1.0));
+            assert_eq!(completed[0][0], 0.0);
+        }
+        assert_eq!(
+            complete_missing(&distances, -1.0, FillStrategy::ShortestPath).unwrap()[0][2],
+            3.0
+        );
+        assert_eq!(
+            complete_missing(&distances, -1.0, FillStrategy::Mean).unwrap()[2][0],
+            2.0
+        );
+        assert_eq!(
+            complete_missing(&distances, -1.0, FillStrategy::Max).unwrap()[2][0],
+            4.0
+        );
+    }
+    #[test]
+    fn test_solve_tsp_without_crossover() {
+        use super::{solve_tsp_core, SolverConfig};
+        // With a crossover rate of 0 offspring are only mutated clones, but
+        // the solver still terminates with valid permutations.
+        let distances = vec![
+            vec![0.0, 1.0, 2.0, 3.0, 4.0],
+            vec![1.0, 0.0, 4.0, 5.0, 6.0],
+            vec![2.0, 4.0, 0.0, 6.0, 7.0],
+            vec![3.0, 5.0, 6.0, 0.0, 8.0],
+            vec![4.0, 6.0, 7.0, 8.0, 0.0],
+        ];
+        let best_routes = solve_tsp_core(
+            &distances,
+            &SolverConfig {
+                n_generations: 50,
+                n_routes: 10,
+                crossover_rate: 0.0,
+                ..SolverConfig::default()
+            },
+        )
+        .routes;
+        assert_eq!(best_routes.len(), 3);
+        for best_route in best_routes {
+            let mut cities = best_route.indexes.clone();
+            cities.sort_unstable();
+            assert_eq!(cities, vec![0, 1, 2, 3, 4]);
+        }
+    }
+    #[test]
+    fn test_two_opt_delta() {
+        use super::{route_distance, two_opt_delta, two_opt_move};
+        // The local delta matches the difference of the full distances, also
+        // for asymmetric matrices.
+        let distances = vec![
+            vec![0.0, 3.0, 9.0, 4.0, 2.0],
+            vec![1.0, 0.0, 6.0, 8.0, 3.0],
+            vec![7.0, 2.0, 0.0, 5.0, 9.0],
+            vec![4.0, 6.0, 3.0, 0.0, 1.0],
+            vec![8.0, 5.0, 2.0, 7.0, 0.0],
+        ];
+        let route = vec![0, 3, 1, 4, 2];
+        for i in 0..route.len() {
+            for j in (i + 1)..route.len() {
+                let expected = route_distance(&distances, &two_opt_move(&route, i, j))
+                    - route_distance(&distances, &route);
+                assert!((two_opt_delta(&distances, &route, i, j) - expected).abs() < 1e-9);
+            }
+        }
+    }
+    #[test]
+    fn test_validate_route() {
+        use super::validate_route;
+        assert!(validate_route(&[0, 1, 2], 3).is_ok());
+        assert!(validate_route(&[0, 1], 3).is_err());
+        assert!(validate_route(&[0, 1, 3], 3).is_err());
+        assert!(validate_route(&[0, 1, 1], 3).is_err());
+    }
+    #[test]
+    fn test_solve_tsp_seeded() {
+        use super::{solve_tsp_core, SolverConfig};
+        // Two runs with the same seed return the same routes.
+        let distances = vec![
+            vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+            vec![1.0, 0.0, 4.0, 5.0, 6.0, 7.0],
+            vec![2.0, 4.0, 0.0, 6.0, 7.0, 8.0],
+            vec![3.0, 5.0, 6.0, 0.0, 8.0, 9.0],
+            vec![4.0, 6.0, 7.0, 8.0, 0.0, 1.0],
+            vec![5.0, 7.0, 8.0, 9.0, 1.0, 0.0],
+        ];
+        let config = SolverConfig {
+            n_generations: 50,
+            n_routes: 10,
+            seed: 42,
+            ..SolverConfig::default()
+        };
+        assert_eq!(
+            solve_tsp_core(&distances, &config),
+            solve_tsp_core(&distances, &config)
+        );
+    }
+    #[test]
+    fn test_crossover_and_mutate_keep_permutations() {
+        use super::{crossover, mutate};
+        use genetic_algorithm_tsp::route::Route;
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let parent_a = Route::new(vec![0, 12, 7, 3, 9, 8, 11, 5, 13, 1, 4, 6, 10, 15, 2, 14]);
+        let parent_b = Route::new(vec![7, 10, 15, 12, 2, 9, 5, 3, 1, 6, 4, 13, 14, 11, 8, 0]);
+        for _ in 0..100 {
+            let mut child = mutate(crossover(&parent_a, &parent_b, &mut rng), 0.5, &mut rng)
+                .indexes
+                .clone();
+            child.sort_unstable();
+            assert_eq!(child, (0..16).collect::<Vec<usize>>());
+        }
+    }
+    #[test]
+    fn test_metric_closure() {
+        use super::{metric_closure, satisfies_triangle_inequality};
+        let distances = vec![
+            vec![0.0, 2.0, 9.0, 10.0],
+            vec![2.0, 0.0, 3.0, 9.0],
+            vec![9.0, 3.0, 0.0, 1.0],
+            vec![10.0, 9.0, 1.0, 0.0],
+        ];
+        assert!(!satisfies_triangle_inequality(&distances, 1e-9));
+        let (closure, n_changed) = metric_closure(&distances);
+        assert!(satisfies_triangle_inequality(&closure, 1e-9));
+        // 0-2, 0-3 and 1-3 are shortened in both directions.
+        assert_eq!(n_changed, 6);
+        assert_eq!(closure[0][3], 6.0);
+    }
+    #[test]
+    fn test_initial_population() {
+        use super::{initial_population, validate_route, InitStrategy};
+        use rand::SeedableRng;
+        use std::collections::HashSet;
+        // Every strategy yields `n_routes` distinct valid routes.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        for init in [
+            InitStrategy::Random,
+            InitStrategy::NearestNeighbor,
+            InitStrategy::GreedyEdge,
+        ] {
+            let population = initial_population(&distances, 20, init, &mut rng);
+            assert_eq!(population.len(), 20);
+            for route in &population {
+                assert!(validate_route(&route.indexes, 6).is_ok());
+            }
+            assert_eq!(
+                population
+                    .iter()
+                    .map(|route| route.indexes.clone())
+                    .collect::<HashSet<Vec<usize>>>()
+                    .len(),
+                20
+            );
+        }
+    }
+    #[test]
+    fn test_greedy_edge_route() {
+        use super::{greedy_edge_route, route_distance, validate_route};
+        // On the 6-city matrix the greedy-edge route is a valid permutation
+        // that is not worse than twice the optimum of 1248.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = greedy_edge_route(&distances);
+        assert!(validate_route(&route, 6).is_ok());
+        assert!(route_distance(&distances, &route) <= 2.0 * 1248.0);
+    }
+    #[test]
+    fn test_canonical_route() {
+        use super::canonical_route;
+        // All rotations and both directions of a tour have the same
+        // canonical form.
+        let route = vec![3, 1, 4, 0, 2];
+        let canonical = canonical_route(&route, true);
+        assert_eq!(canonical, vec![0, 2, 3, 1, 4]);
+        for shift in 0..route.len() {
+            let mut rotated = route.clone();
+            rotated.rotate_left(shift);
+            assert_eq!(canonical_route(&rotated, true), canonical);
+            rotated.reverse();
+            assert_eq!(canonical_route(&rotated, true), canonical);
+        }
+        // Without `reversible` the direction is kept.
+        assert_eq!(
+            canonical_route(&[0, 4, 1, 3, 2], false),
+            vec![0, 4, 1, 3, 2]
+        );
+    }
+    #[test]
+    fn test_mst_lower_bound() {
+        use super::mst_lower_bound;
+        // The MST never exceeds the optimal tour of the 6-city matrix.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let lower_bound = mst_lower_bound(&distances);
+        assert_eq!(lower_bound, 64.0 + 164.0 + 273.0 + 223.0 + 170.0);
+        assert!(lower_bound <= 1248.0);
+    }
+    #[test]
+    fn test_validate_distance_matrix() {
+        use super::validate_distance_matrix;
+        assert!(validate_distance_matrix(&[vec![0.0]]).is_ok());
+        assert!(validate_distance_matrix(&[]).is_err());
+        assert!(validate_distance_matrix(&[vec![0.0, 1.0], vec![1.0]]).is_err());
+        assert!(validate_distance_matrix(&[vec![0.0, -1.0], vec![1.0, 0.0]]).is_err());
+    }
+    #[test]
+    fn test_matrix_hash() {
+        use super::matrix_hash;
+        let distances = vec![
+            vec![0.0, 1.0, 2.0],
+            vec![1.0, 0.0, 3.0],
+            vec![2.0, 3.0, 0.0],
+        ];
+        // The hash is stable and changes with every cell.
+        assert_eq!(matrix_hash(&distances, None), matrix_hash(&distances, None));
+        assert_eq!(matrix_hash(&distances, None).len(), 16);
+        let mut changed = distances.clone();
+        changed[1][2] = 3.5;
+        assert_ne!(matrix_hash(&distances, None), matrix_hash(&changed, None));
+        // Matrices with the same values but a different shape differ.
+        assert_ne!(
+            matrix_hash(&[vec![0.0, 0.0]], None),
+            matrix_hash(&[vec![0.0], vec![0.0]], None)
+        );
+    }
+    #[test]
+    fn test_two_opt_max_passes() {
+        use super::{route_distance, two_opt};
+        let distances = vec![
+            vec![0.0, 141.0, 134.0, 152.0, 173.0, 289.0, 326.0],
+            vec![141.0, 0.0, 152.0, 150.0, 153.0, 312.0, 354.0],
+            vec![134.0, 152.0, 0.0, 24.0, 48.0, 168.0, 210.0],
+            vec![152.0, 150.0, 24.0, 0.0, 24.0, 163.0, 206.0],
+            vec![173.0, 153.0, 48.0, 24.0, 0.0, 160.0, 203.0],
+            vec![289.0, 312.0, 168.0, 163.0, 160.0, 0.0, 63.0],
+            vec![326.0, 354.0, 210.0, 206.0, 203.0, 63.0, 0.0],
+        ];
+        let route = vec![0, 5, 2, 6, 1, 3, 4];
+        // The number of passes is bounded and the result is never worse
+        // than the input.
+        for max_passes in 1..4 {
+            let (refined, n_passes) = two_opt(&distances, &route, Some(max_passes));
+            assert!(n_passes <= max_passes);
+            assert!(route_distance(&distances, &refined) <= route_distance(&distances, &route));
+        }
+        // Without a bound 2-opt runs until no move improves the route.
+        let (refined, _) = two_opt(&distances, &route, None);
+        assert!(super::best_two_opt_move(&distances, &refined).is_none());
+    }
+    #[test]
+    fn test_durations_to_distances() {
+        use super::durations_to_distances;
+        let durations = vec![
+            vec![0.0, 1.5, 2.0],
+            vec![1.5, 0.0, 0.5],
+            vec![2.0, 0.5, 0.0],
+        ];
+        let distances = durations_to_distances(&durations, 4.0).unwrap();
+        assert_eq!(distances[0][1], 6.0);
+        assert_eq!(distances[1][2], 2.0);
+        assert!((0..3).all(|city| distances[city][city] == 0.0));
+        assert!(durations_to_distances(&durations, 0.0).is_err());
+        assert!(durations_to_distances(&[vec![0.0, -1.0], vec![1.0, 0.0]], 1.0).is_err());
+    }
+    #[test]
+    fn test_solve_tsp_avg_history() {
+        use super::{route_distance, solve_tsp_core, SolverConfig};
+        // There is one average per generation and the final average of the
+        // population is never below its best route.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let outcome = solve_tsp_core(
+            &distances,
+            &SolverConfig {
+                n_generations: 100,
+                seed: 5,
+                ..SolverConfig::default()
+            },
+        );
+        assert_eq!(outcome.generations_run, 100);
+        assert_eq!(outcome.avg_history.len(), outcome.generations_run);
+        let best = route_distance(&distances, &outcome.routes[0].indexes);
+        assert!(*outcome.avg_history.last().unwrap() >= best);
+    }
+    #[test]
+    fn test_is_two_opt_optimal() {
+        use super::{is_two_opt_optimal, two_opt};
+        // A route with crossing legs is not 2-opt optimal, after refining
+        // it with 2-opt it is.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let crossing = vec![0, 3, 1, 4, 2, 5];
+        assert!(!is_two_opt_optimal(&distances, &crossing));
+        let (refined, _) = two_opt(&distances, &crossing, None);
+        assert!(is_two_opt_optimal(&distances, &refined));
+    }
+    #[test]
+    fn test_population_diversity() {
+        use super::population_diversity;
+        use genetic_algorithm_tsp::route::Route;
+        // Routes without common legs are maximally diverse.
+        let disjoint = vec![
+            Route::new(vec![0, 1, 2, 3, 4]),
+            Route::new(vec![0, 2, 4, 1, 3]),
+        ];
+        assert_eq!(population_diversity(&disjoint), 1.0);
+        let same = vec![Route::new(vec![0, 1, 2, 3]), Route::new(vec![3, 2, 1, 0])];
+        assert_eq!(population_diversity(&same), 0.5);
+    }
+    #[test]
+    fn test_repair_route() {
+        use super::{repair_route, validate_route, RouteEdit};
+        // Valid routes are returned unchanged.
+        assert_eq!(repair_route(&[2, 0, 1], 3), (vec![2, 0, 1], vec![]));
+        // Too long routes lose their duplicates, too short ones are extended.
+        let (repaired, edits) = repair_route(&[1, 0, 1, 7], 2);
+        assert_eq!(repaired, vec![1, 0]);
+        assert_eq!(
+            edits,
+            vec![
+                RouteEdit::Remove { index: 2, city: 1 },
+                RouteEdit::Remove { index: 3, city: 7 }
+            ]
+        );
+        let (repaired, edits) = repair_route(&[3, 1], 4);
+        assert!(validate_route(&repaired, 4).is_ok());
+        assert_eq!(
+            edits,
+            vec![RouteEdit::Append { city: 0 }, RouteEdit::Append { city: 2 }]
+        );
+    }
+    #[test]
+    fn test_hop_distances() {
+        use super::hop_distances;
+        // Two components: the path 0 - 1 - 2 - 3 and the single city 4.
+        let hops = hop_distances(5, &[[0, 1], [2, 1], [2, 3]]).unwrap();
+        assert_eq!(hops[0][3], 3.0);
+        assert_eq!(hops[3][0], 3.0);
+        assert_eq!(hops[1][3], 2.0);
+        assert!(hops[0][4].is_infinite());
+        assert!(hop_distances(2, &[[0, 2]]).is_err());
+    }
+    #[test]
+    fn test_haversine_matrix() {
+        use super::haversine_matrix;
+        // Berlin to Paris is about 878km.
+        let distances = haversine_matrix(&[[52.52, 13.405], [48.8566, 2.3522]]).unwrap();
+        assert!((distances[0][1] - 878.0).abs() < 2.0);
+        assert_eq!(distances[0][1], distances[1][0]);
+        assert_eq!(distances[0][0], 0.0);
+        assert!(haversine_matrix(&[[91.0, 0.0]]).is_err());
+    }
+    #[test]
+    fn test_k_medoids() {
+        use super::{euclidean_matrix_3d, k_medoids};
+        // Three well separated groups of points are found as clusters.
+        let distances = euclidean_matrix_3d(&[
+            [0.0, 0.0, 0.0],
+            [10.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [10.0, 1.0, 0.0],
+            [5.0, 9.0, 0.0],
+            [1.0, 0.0, 0.0],
+        ]);
+        let clustering = k_medoids(&distances, 3).unwrap();
+        let mut clusters = clustering.clusters.clone();
+        clusters.sort();
+        assert_eq!(clusters, vec![vec![0, 2, 5], vec![1, 3], vec![4]]);
+        for (medoid, members) in clustering.medoids.iter().zip(clustering.clusters.iter()) {
+            assert!(members.contains(medoid));
+        }
+        assert!(k_medoids(&distances, 0).is_err());
+        assert!(k_medoids(&distances, 7).is_err());
+    }
+    #[test]
+    fn test_diverse_injection() {
+        use super::*;
+        use rand::rngs;
+        let points = (0..20)
+            .map(|city| {
+                [
+                    f64::from((city * 37) % 101),
+                    f64::from((city * 53) % 97),
+                    0.0,
+                ]
+            })
+            .collect::<Vec<[f64; 3]>>();
+        let distances = euclidean_matrix_3d(&points);
+        // Diverse injection adds routes that increase the diversity of the
+        // population more than random ones.
+        let mut rng = rngs::StdRng::seed_from_u64(3);
+        let population = random_routes(30, 20, &mut rng);
+        let with_injected = |injected: Vec<route::Route>| {
+            population_diversity(&[population.clone(), injected].concat())
+        };
+        let mut rng = rngs::StdRng::seed_from_u64(4);
+        let random = with_injected(random_routes(10, 20, &mut rng));
+        let mut rng = rngs::StdRng::seed_from_u64(4);
+        let diverse = with_injected(diverse_routes(&population, 10, 20, &mut rng));
+        assert!(diverse > random);
+        // The algorithm still converges with diverse injection.
+        let outcome = solve_tsp_core(
+            &distances,
+            &SolverConfig {
+                n_generations: 100,
+                seed: 0,
+                injection: Injection::Diverse,
+                ..Default::default()
+            },
+        );
+        assert!(outcome.best_history.last().unwrap() < &(0.6 * outcome.best_history[0]));
+        assert!(outcome
+            .best_history
+            .windows(2)
+            .all(|pair| pair[1] <= pair[0]));
+    }
+    #[test]
+    fn test_cheapest_insertion() {
+        use super::{cheapest_insertion, route_distance};
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = vec![0, 2, 3, 4, 5];
+        let (inserted, _, added) = cheapest_insertion(&distances, &route, 1).unwrap();
+        // No other position adds less distance.
+        for position in 0..=route.len() {
+            let mut candidate = route.clone();
+            candidate.insert(position, 1);
+            assert!(
+                route_distance(&distances, &candidate) >= route_distance(&distances, &inserted)
+            );
+        }
+        assert!(
+            (route_distance(&distances, &inserted) - route_distance(&distances, &route) - added)
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(cheapest_insertion(&distances, &[], 3).unwrap().0, vec![3]);
+        assert!(cheapest_insertion(&distances, &route, 2).is_err());
+        assert!(cheapest_insertion(&distances, &route, 6).is_err());
+    }
+    #[test]
+    fn test_best_or_opt_move() {
+        use super::{best_or_opt_move, or_opt_move, route_distance};
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        // The reported delta matches the change of distance.
+        let route = vec![0, 3, 1, 4, 2, 5];
+        let (i, length, k, delta) = best_or_opt_move(&distances, &route).unwrap();
+        let moved = or_opt_move(&route, i, length, k);
+        assert!(delta < 0.0);
+        assert!(
+            (route_distance(&distances, &moved) - route_distance(&distances, &route) - delta).abs()
+                < 1e-9
+        );
+        // The optimal tour cannot be improved.
+        assert!(best_or_opt_move(&distances, &[0, 1, 2, 3, 4, 5]).is_none());
+        assert!(best_or_opt_move(&distances, &[0, 1]).is_none());
+    }
+    #[test]
+    fn test_fittest_tie_break() {
+        use super::{fittest, Objective, TieBreak};
+        use genetic_algorithm_tsp::route::Route;
+        // Both tours have a distance of 8, the second one with a longest leg
+        // of 3 instead of 5.
+        let distances = vec![
+            vec![0.0, 1.0, 3.0, 5.0],
+            vec![1.0, 0.0, 1.0, 3.0],
+            vec![3.0, 1.0, 0.0, 1.0],
+            vec![5.0, 3.0, 1.0, 0.0],
+        ];
+        let unbalanced = Route::new(vec![0, 1, 2, 3]);
+        let balanced = Route::new(vec![0, 1, 3, 2]);
+        let longer = Route::new(vec![0, 2, 1, 3]);
+        let routes = vec![longer, unbalanced.clone(), balanced.clone()];
+        assert_eq!(
+            fittest(
+                routes.clone(),
+                2,
+                &distances,
+                Objective::Distance,
+                TieBreak::None
+            ),
+            vec![unbalanced.clone(), balanced.clone()]
+        );
+        assert_eq!(
+            fittest(routes, 2, &distances, Objective::Distance, TieBreak::MaxLeg),
+            vec![balanced, unbalanced]
+        );
+    }
+    #[test]
+    fn test_solve_tsp_core_lineage() {
+        use super::{solve_tsp_core, Operator, SolverConfig};
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let config = SolverConfig {
+            n_generations: 50,
+            seed: 5,
+            ..Default::default()
+        };
+        // Tracking the lineage does not change the result.
+        let untracked = solve_tsp_core(&distances, &config);
+        let tracked = solve_tsp_core(
+            &distances,
+            &SolverConfig {
+                track_lineage: true,
+                ..config
+            },
+        );
+        assert_eq!(untracked.lineage, None);
+        assert_eq!(tracked.routes, untracked.routes);
+        // The lineage starts at a route without parent and ends at the best
+        // route, with generations increasing along the way.
+        let lineage = tracked.lineage.unwrap();
+        assert!([Operator::Initial, Operator::Random].contains(&lineage[0].operator));
+        assert_eq!(lineage.last().unwrap().distance, tracked.best_history[49]);
+        assert!(lineage
+            .windows(2)
+            .all(|pair| pair[0].generation < pair[1].generation));
+    }
+    #[test]
+    fn test_mutation_ops() {
+        use super::{mutate_with, route, route_distance, solve_tsp_core, MutationOp, SolverConfig};
+        use rand::{rngs, SeedableRng};
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        for op in [MutationOp::Swap, MutationOp::Insert, MutationOp::Invert] {
+            let mut rng = rngs::StdRng::seed_from_u64(5);
+            let mut route = route::Route::new(vec![0, 1, 2, 3, 4, 5]);
+            for _ in 0..50 {
+                let mutated = mutate_with(route.clone(), 1.0, op, &mut rng);
+                assert_ne!(mutated, route);
+                let mut cities = mutated.indexes.clone();
+                cities.sort_unstable();
+                assert_eq!(cities, vec![0, 1, 2, 3, 4, 5]);
+                route = mutated;
+            }
+            let outcome = solve_tsp_core(
+                &distances,
+                &SolverConfig {
+                    n_generations: 100,
+                    seed: 0,
+                    mutation_op: op,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(
+                route_distance(&distances, &outcome.routes[0].indexes),
+                1248.0
+            );
+        }
+    }
+    #[test]
+    fn test_candidate_two_opt() {
+        use super::{candidate_two_opt, route_distance, route_legs, two_opt};
+        use std::collections::HashSet;
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let route = vec![0, 3, 1, 4, 2, 5];
+        let legs = |route: &[usize]| {
+            route_legs(&distances, route)
+                .into_iter()
+                .map(|(from, to, _)| (from.min(to), from.max(to)))
+                .collect::<HashSet<(usize, usize)>>()
+        };
+        // Only the legs of the optimal tour are candidates.
+        let candidates = vec![vec![1, 5], vec![2], vec![3], vec![4], vec![5], vec![]];
+        let (restricted, _) = candidate_two_opt(&distances, &route, &candidates, None);
+        let allowed = &legs(&route) | &legs(&[0, 1, 2, 3, 4, 5]);
+        assert!(legs(&restricted).is_subset(&allowed));
+        assert!(route_distance(&distances, &restricted) < route_distance(&distances, &route));
+        // With all cities as candidates it is plain 2-opt.
+        let everyone = (0..6)
+            .map(|city| (0..6).filter(|&other| other != city).collect())
+            .collect::<Vec<Vec<usize>>>();
+        assert_eq!(
+            candidate_two_opt(&distances, &route, &everyone, None),
+            two_opt(&distances, &route, None)
+        );
+    }
+    #[test]
+    fn test_min_improvement() {
+        use super::*;
+        let points = (0..20)
+            .map(|city| {
+                [
+                    f64::from((city * 37) % 101),
+                    f64::from((city * 53) % 97),
+                    0.0,
+                ]
+            })
+            .collect::<Vec<[f64; 3]>>();
+        let distances = euclidean_matrix_3d(&points);
+        let solve = |min_improvement: f64| {
+            solve_tsp_core(
+                &distances,
+                &SolverConfig {
+                    n_generations: 200,
+                    seed: 1,
+                    patience: Some(5),
+                    min_improvement,
+                    ..Default::default()
+                },
+            )
+        };
+        // No improvement is big enough, so the run stops after the
+        // patience is used up.
+        let strict = solve(1e9);
+        assert_eq!(strict.generations_run, 5);
+        assert_eq!(strict.stop_reason, StopReason::Patience);
+        let lenient = solve(0.0);
+        assert!(lenient.generations_run > strict.generations_run);
+    }
+    #[test]
+    fn test_edit_delta() {
+        use super::{apply_edit, edit_delta, euclidean_matrix_3d, route_distance, TourEdit};
+
+        // The local delta of every edit matches the full recomputation.
+        let distances = euclidean_matrix_3d(&[
+            [0.0, 0.0, 0.0],
+            [3.0, 1.0, 0.0],
+            [1.0, 4.0, 0.0],
+            [5.0, 2.0, 1.0],
+            [2.0, 2.0, 3.0],
+            [4.0, 0.0, 2.0],
+        ]);
+        let route = [3, 0, 5, 1, 4, 2];
+        let before = route_distance(&distances, &route);
+        for first in 0..route.len() {
+            for second in 0..route.len() {
+                for edit in [
+                    TourEdit::Swap {
+                        i: first,
+                        j: second,
+                    },
+                    TourEdit::Move {
+                        from: first,
+                        to: second,
+                    },
+                ] {
+                    let after = route_distance(&distances, &apply_edit(&route, edit));
+                    let delta = edit_delta(&distances, &route, edit).unwrap();
+                    assert!((delta - (after - before)).abs() < 1e-9, "{:?}", edit);
+                }
+            }
+        }
+        assert!(edit_delta(&distances, &route, TourEdit::Swap { i: 0, j: 6 }).is_err());
+    }
+    #[test]
+    fn test_k_opt_neighbors() {
+        use super::{k_opt_neighbors, pure_reconnections};
+
+        // The 2-opt neighborhood of a closed tour has n * (n - 3) / 2 moves.
+        for n_cities in 4..50 {
+            assert_eq!(
+                k_opt_neighbors(n_cities, 2),
+                Ok((n_cities * (n_cities - 3) / 2) as u64)
+            );
+        }
+        // Counted by enumerating all tours that lack exactly `k`
+        // non-adjacent edges of the tour through the cities in order.
+        assert_eq!(
+            (2..=5).map(pure_reconnections).collect::<Vec<u64>>(),
+            vec![1, 4, 25, 208]
+        );
+        assert_eq!(k_opt_neighbors(6, 3), Ok(8));
+        assert_eq!(k_opt_neighbors(8, 4), Ok(50));
+        assert_eq!(k_opt_neighbors(12, 5), Ok(7488));
+        assert_eq!(k_opt_neighbors(3, 2), Ok(0));
+        assert!(k_opt_neighbors(10, 1).is_err());
+        assert!(k_opt_neighbors(usize::MAX / 2, 8).is_err());
+    }
+    #[test]
+    fn test_count_crossings() {
+        use super::{count_crossings, euclidean_matrix_3d, two_opt};
+
+        // A tour that crosses itself twice has no crossing after 2-opt.
+        let points = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 2.0],
+            [2.0, 2.0],
+            [0.0, 2.0],
+        ];
+        let distances = euclidean_matrix_3d(
+            &points
+                .iter()
+                .map(|&[x, y]| [x, y, 0.0])
+                .collect::<Vec<[f64; 3]>>(),
+        );
+        let crossing = [0, 4, 2, 3, 1, 5];
+        assert!(count_crossings(&crossing, &points) > 0);
+        let (refined, _) = two_opt(&distances, &crossing, None);
+        assert_eq!(count_crossings(&refined, &points), 0);
+    }
+    #[test]
+    fn test_check_interval() {
+        use super::{solve_tsp_core, SolverConfig};
+
+        // Checking less often changes neither the routes nor the histories
+        // of every generation, but runs fewer checks.
+        let distances = vec![
+            vec![0.0, 64.0, 378.0, 519.0, 434.0, 200.0],
+            vec![64.0, 0.0, 318.0, 455.0, 375.0, 164.0],
+            vec![378.0, 318.0, 0.0, 170.0, 265.0, 344.0],
+            vec![519.0, 455.0, 170.0, 0.0, 223.0, 428.0],
+            vec![434.0, 375.0, 265.0, 223.0, 0.0, 273.0],
+            vec![200.0, 164.0, 344.0, 428.0, 273.0, 0.0],
+        ];
+        let solve = |check_interval: usize, patience: Option<usize>| {
+            solve_tsp_core(
+                &distances,
+                &SolverConfig {
+                    n_generations: 100,
+                    seed: 11,
+                    check_interval,
+                    patience,
+                    ..SolverConfig::default()
+                },
+            )
+        };
+        let every = solve(1, None);
+        let batched = solve(10, None);
+        assert_eq!(batched.routes, every.routes);
+        assert_eq!(batched.best_history, every.best_history);
+        assert_eq!(every.checks_run, 100);
+        assert_eq!(batched.checks_run, 10);
+        assert_eq!(batched.diversity_history.len(), 10);
+        // Patience still stops the run, at most one interval late.
+        let every = solve(1, Some(5));
+        let batched = solve(10, Some(5));
+        assert_eq!(batched.stop_reason, super::StopReason::Patience);
+        assert!(batched.generations_run >= every.generations_run);
+        assert!(batched.generations_run < every.generations_run + 10);
+    }
+    #[test]
+    fn test_cluster_solve_invalid_config() {
+        use super::{cluster_solve, euclidean_matrix_3d, SolverConfig};
+
+        // The configuration is rejected even if every cluster is too small
+        // to be solved.
+        let distances = euclidean_matrix_3d(&[
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [10.0, 0.0, 0.0],
+            [11.0, 0.0, 0.0],
+        ]);
+        for config in [
+            SolverConfig {
+                n_generations: 0,
+                ..SolverConfig::default()
+            },
+            SolverConfig {
+                n_generations: 20000,
+                ..SolverConfig::default()
+            },
+            SolverConfig {
+                n_generations: 10,
+                top_n: 0,
+                ..SolverConfig::default()
+            },
+        ] {
+            assert!(cluster_solve(&distances, 2, &config).is_err());
+        }
+    }
 }